@@ -0,0 +1,511 @@
+use bevy::input::gamepad::GamepadEventType;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::{Deref, DerefMut};
+
+use crate::camera::{cursor_to_world, MainCamera};
+use crate::synthesis::STORAGE_SIZE;
+
+/// Player bindings, read from the working directory and written back when edited.
+pub const CONTROLS_PATH: &str = "controls.ron";
+
+/// Stick deflection below which the left stick doesn't move the player
+/// and sticks don't make the gamepad the last used device.
+const STICK_DEADZONE: f32 = 0.2;
+/// Right stick deflection needed to aim, so the facing doesn't snap back as the stick recenters.
+const AIM_DEADZONE: f32 = 0.5;
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActionMap::load_or_default(CONTROLS_PATH))
+            .init_resource::<ActionState>()
+            .init_resource::<Rebind>()
+            .init_resource::<ConnectedGamepads>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_connection_system
+                    .before("action_state")
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_action_state
+                    .label("action_state")
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                cursor_system.label("action_state").after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                rebind_system.before("action_state").after(InputSystem),
+            );
+    }
+}
+
+/// Everything the player can do, independently of the device.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Grab,
+    Throw,
+    Store,
+    Hold,
+    Synthesize,
+    Clear,
+    /// Select a storage slot, from 0.
+    Slot(usize),
+    NextSlot,
+    PrevSlot,
+    /// Add the item in the selected slot to the blueprint, like selecting the slot twice.
+    AddToBlueprint,
+    Confirm,
+    Pause,
+    MenuUp,
+    MenuDown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// The button on any connected gamepad.
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+}
+
+/// The bindings of every action. An action can have several bindings, or none.
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use Action::*;
+        use Binding::*;
+        use GamepadButtonType as Pad;
+        let mut bindings = HashMap::default();
+        bindings.insert(MoveUp, vec![Key(KeyCode::W)]);
+        bindings.insert(MoveDown, vec![Key(KeyCode::S)]);
+        bindings.insert(MoveLeft, vec![Key(KeyCode::A)]);
+        bindings.insert(MoveRight, vec![Key(KeyCode::D)]);
+        bindings.insert(Grab, vec![Mouse(MouseButton::Left), Gamepad(Pad::LeftTrigger2)]);
+        bindings.insert(Throw, vec![Key(KeyCode::Space), Gamepad(Pad::RightTrigger2)]);
+        bindings.insert(Store, vec![Key(KeyCode::F), Gamepad(Pad::West)]);
+        bindings.insert(Hold, vec![Key(KeyCode::E), Gamepad(Pad::North)]);
+        bindings.insert(Synthesize, vec![Key(KeyCode::Q), Gamepad(Pad::South)]);
+        bindings.insert(Clear, vec![Key(KeyCode::C), Gamepad(Pad::East)]);
+        bindings.insert(NextSlot, vec![Gamepad(Pad::RightTrigger), Gamepad(Pad::DPadRight)]);
+        bindings.insert(PrevSlot, vec![Gamepad(Pad::LeftTrigger), Gamepad(Pad::DPadLeft)]);
+        bindings.insert(AddToBlueprint, vec![Gamepad(Pad::DPadUp)]);
+        let slots = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+        ];
+        for (i, key) in slots.iter().enumerate().take(STORAGE_SIZE) {
+            bindings.insert(Slot(i), vec![Key(*key)]);
+        }
+        bindings.insert(Confirm, vec![Key(KeyCode::Return), Gamepad(Pad::South)]);
+        bindings.insert(Pause, vec![Key(KeyCode::Escape), Gamepad(Pad::Start)]);
+        bindings.insert(MenuUp, vec![Key(KeyCode::Up), Gamepad(Pad::DPadUp)]);
+        bindings.insert(MenuDown, vec![Key(KeyCode::Down), Gamepad(Pad::DPadDown)]);
+        ActionMap { bindings }
+    }
+}
+
+impl ActionMap {
+    /// Actions missing from the file keep their default bindings.
+    /// A missing or malformed file falls back on the defaults.
+    pub fn load_or_default(path: &str) -> Self {
+        let mut map = ActionMap::default();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(_) => return map,
+        };
+        match ron::de::from_str::<Vec<(Action, Vec<Binding>)>>(&text) {
+            Ok(entries) => {
+                for (action, bindings) in entries {
+                    map.bindings.insert(action, bindings);
+                }
+            }
+            Err(e) => error!("{}: {}, using default controls", path, e),
+        }
+        map
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut entries: Vec<(Action, Vec<Binding>)> = self
+            .bindings
+            .iter()
+            .map(|(action, bindings)| (*action, bindings.clone()))
+            .collect();
+        entries.sort_by_key(|(action, _)| *action);
+        let result = ron::ser::to_string_pretty(&entries, Default::default())
+            .map_err(anyhow::Error::from)
+            .and_then(|text| fs::write(path, text).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            error!("could not save controls to {}: {}", path, e);
+        }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Make `binding` the binding of `action` on its device, replacing the previous one.
+    /// Keyboard and mouse count as one device.
+    /// A binding already used by another action is refused and that action is returned.
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        let used_by = self
+            .bindings
+            .iter()
+            .find(|(other, bindings)| **other != action && bindings.contains(&binding));
+        if let Some((other, _)) = used_by {
+            return Err(*other);
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Device {
+    KeyboardMouse,
+    Gamepad(Gamepad),
+}
+
+impl Default for Device {
+    fn default() -> Self {
+        Device::KeyboardMouse
+    }
+}
+
+/// Which actions are held this frame, and which started or stopped.
+/// Systems read this instead of the devices, gameplay reads `StepActions`.
+#[derive(Clone, Default, Debug)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    /// Actions that started or stopped since the last simulation step.
+    step_pressed: HashSet<Action>,
+    step_released: HashSet<Action>,
+    /// The device that was used last. Aiming follows the cursor or the right stick accordingly.
+    pub device: Device,
+    /// Movement direction, from the move actions or the left stick. Each axis is in `-1.0..=1.0`.
+    pub movement: Vec2,
+    /// Facing requested with the right stick, if the gamepad was used last and the stick is
+    /// pushed. `None` with a gamepad keeps the current facing.
+    pub aim: Option<Vec2>,
+    /// Cursor position in the world, in rapier units, if it is over the window.
+    pub cursor: Option<Vec2>,
+}
+
+/// The input of the current simulation step, see `fixed_step`. An action that starts or stops
+/// between two steps is seen by exactly one step, however many steps a frame runs.
+#[derive(Default, Debug)]
+pub struct StepActions(pub ActionState);
+
+impl Deref for StepActions {
+    type Target = ActionState;
+
+    fn deref(&self) -> &ActionState {
+        &self.0
+    }
+}
+
+impl DerefMut for StepActions {
+    fn deref_mut(&mut self) -> &mut ActionState {
+        &mut self.0
+    }
+}
+
+/// Everything gameplay reads from `StepActions` in a step, as stored in replays.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct ActionFrame {
+    pub pressed: Vec<Action>,
+    pub just_pressed: Vec<Action>,
+    pub just_released: Vec<Action>,
+    pub gamepad: bool,
+    pub movement: Vec2,
+    pub aim: Option<Vec2>,
+    pub cursor: Option<Vec2>,
+}
+
+fn sorted(actions: &HashSet<Action>) -> Vec<Action> {
+    let mut actions: Vec<Action> = actions.iter().copied().collect();
+    actions.sort();
+    actions
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Hold or release `action`, as a device would.
+    pub fn set(&mut self, action: Action, pressed: bool) {
+        if pressed && self.pressed.insert(action) {
+            self.just_pressed.insert(action);
+            self.step_pressed.insert(action);
+        } else if !pressed && self.pressed.remove(&action) {
+            self.just_released.insert(action);
+            self.step_released.insert(action);
+        }
+    }
+
+    /// The state a simulation step sees: what is held now, and what started or stopped since
+    /// the previous step.
+    pub fn take_step(&mut self) -> ActionState {
+        ActionState {
+            just_pressed: std::mem::take(&mut self.step_pressed),
+            just_released: std::mem::take(&mut self.step_released),
+            ..self.clone()
+        }
+    }
+
+    /// Forget which actions started or stopped, before a new frame of input.
+    pub fn clear_just(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+
+    /// This state, with the actions sorted so equal frames compare equal.
+    pub fn frame(&self) -> ActionFrame {
+        ActionFrame {
+            pressed: sorted(&self.pressed),
+            just_pressed: sorted(&self.just_pressed),
+            just_released: sorted(&self.just_released),
+            gamepad: matches!(self.device, Device::Gamepad(_)),
+            movement: self.movement,
+            aim: self.aim,
+            cursor: self.cursor,
+        }
+    }
+
+    /// Replace this state with a recorded one.
+    pub fn apply_frame(&mut self, frame: &ActionFrame) {
+        self.pressed = frame.pressed.iter().copied().collect();
+        self.just_pressed = frame.just_pressed.iter().copied().collect();
+        self.just_released = frame.just_released.iter().copied().collect();
+        self.device = if frame.gamepad {
+            Device::Gamepad(Gamepad(0))
+        } else {
+            Device::KeyboardMouse
+        };
+        self.movement = frame.movement;
+        self.aim = frame.aim;
+        self.cursor = frame.cursor;
+    }
+}
+
+/// Forget the presses that led to a state, so they don't also trigger an action bound to
+/// the same button in it, e.g. Confirm and Synthesize on the gamepad's south button.
+pub fn clear_action_edges(mut actions: ResMut<ActionState>) {
+    actions.clear_just();
+    // nor in the next step
+    actions.take_step();
+}
+
+#[derive(Default, Debug)]
+pub struct ConnectedGamepads(pub HashSet<Gamepad>);
+
+fn gamepad_connection_system(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut gamepads: ResMut<ConnectedGamepads>,
+) {
+    for GamepadEvent(gamepad, event_type) in gamepad_events.iter() {
+        match event_type {
+            GamepadEventType::Connected => {
+                gamepads.0.insert(*gamepad);
+            }
+            GamepadEventType::Disconnected => {
+                gamepads.0.remove(gamepad);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn stick(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+) -> Vec2 {
+    Vec2::new(
+        axes.get(GamepadAxis(gamepad, x)).unwrap_or(0.0),
+        axes.get(GamepadAxis(gamepad, y)).unwrap_or(0.0),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_action_state(
+    map: Res<ActionMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<ConnectedGamepads>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut state: ResMut<ActionState>,
+) {
+    state.clear_just();
+
+    let left_stick = |gamepad| {
+        stick(&axes, gamepad, GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)
+    };
+    let right_stick = |gamepad| {
+        stick(&axes, gamepad, GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)
+    };
+    if keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || cursor_moved.iter().next().is_some()
+    {
+        state.device = Device::KeyboardMouse;
+    }
+    for &gamepad in gamepads.0.iter() {
+        if gamepad_buttons
+            .get_just_pressed()
+            .any(|button| button.0 == gamepad)
+            || left_stick(gamepad).length() > STICK_DEADZONE
+            || right_stick(gamepad).length() > STICK_DEADZONE
+        {
+            state.device = Device::Gamepad(gamepad);
+        }
+    }
+
+    for (action, bindings) in map.bindings.iter() {
+        let pressed = bindings.iter().any(|binding| match binding {
+            Binding::Key(key) => keys.pressed(*key),
+            Binding::Mouse(button) => mouse.pressed(*button),
+            Binding::Gamepad(button_type) => gamepads
+                .0
+                .iter()
+                .any(|&gamepad| gamepad_buttons.pressed(GamepadButton(gamepad, *button_type))),
+        });
+        state.set(*action, pressed);
+    }
+
+    let mut movement = Vec2::ZERO;
+    if state.pressed(Action::MoveLeft) {
+        movement.x -= 1.0;
+    }
+    if state.pressed(Action::MoveRight) {
+        movement.x += 1.0;
+    }
+    if state.pressed(Action::MoveUp) {
+        movement.y += 1.0;
+    }
+    if state.pressed(Action::MoveDown) {
+        movement.y -= 1.0;
+    }
+    state.aim = None;
+    if let Device::Gamepad(gamepad) = state.device {
+        let left = left_stick(gamepad);
+        if movement == Vec2::ZERO && left.length() > STICK_DEADZONE {
+            movement = left.clamp_length_max(1.0);
+        }
+        let right = right_stick(gamepad);
+        if right.length() > AIM_DEADZONE {
+            state.aim = Some(right.normalize());
+        }
+    }
+    state.movement = movement;
+}
+
+/// The camera is where the previous frame left it, as for every system this frame.
+fn cursor_system(
+    windows: Res<Windows>,
+    camera: Query<(&GlobalTransform, &OrthographicProjection), With<MainCamera>>,
+    mut state: ResMut<ActionState>,
+) {
+    state.cursor = match (windows.get_primary(), camera.get_single()) {
+        (Some(window), Ok((camera_transform, projection))) => {
+            cursor_to_world(window, camera_transform, projection)
+        }
+        _ => None,
+    };
+}
+
+/// While `action` is set, the next key or mouse button pressed is bound to it and saved.
+/// A press already bound to another action is refused instead, and kept in `refused`
+/// until the next rebind.
+#[derive(Default, Debug)]
+pub struct Rebind {
+    pub action: Option<Action>,
+    pub refused: Option<RefusedBinding>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RefusedBinding {
+    pub action: Action,
+    pub binding: Binding,
+    pub used_by: Action,
+}
+
+fn rebind_system(
+    mut rebind: ResMut<Rebind>,
+    mut map: ResMut<ActionMap>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+) {
+    let action = match rebind.action {
+        Some(action) => action,
+        None => return,
+    };
+    let binding = if let Some(key) = keys.get_just_pressed().next() {
+        Binding::Key(*key)
+    } else if let Some(button) = mouse.get_just_pressed().next() {
+        Binding::Mouse(*button)
+    } else {
+        return;
+    };
+    rebind.action = None;
+    match map.bind(action, binding) {
+        Ok(()) => map.save(CONTROLS_PATH),
+        Err(used_by) => {
+            rebind.refused = Some(RefusedBinding {
+                action,
+                binding,
+                used_by,
+            });
+        }
+    }
+    // the captured press shouldn't also trigger an action
+    match binding {
+        Binding::Key(key) => keys.reset(key),
+        Binding::Mouse(button) => mouse.reset(button),
+        Binding::Gamepad(_) => {}
+    }
+}
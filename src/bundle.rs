@@ -5,17 +5,26 @@ use bevy_rapier2d::prelude::*;
 
 use super::RAPIER_TO_LYON;
 use crate::component::*;
-use crate::magic::*;
+use crate::damage::{ImpactThreshold, Resistances, DEFAULT_IMPACT_THRESHOLD};
+use crate::enemy::{AiController, ENEMY_COLOR, ENEMY_HP};
+use crate::fixed_step::Interpolated;
+use crate::object_def::*;
 use crate::shape_mod::*;
 use crate::synthesis::*;
 use crate::SpriteAtlasHandle;
 use bevy::ecs::system::EntityCommands;
 
+/// Health of the player at the start of a run.
+pub const PLAYER_HP: i32 = 100;
+
+/// What the player and enemies are made of: a body that holds, stores and throws objects.
 #[derive(Bundle)]
-pub struct PlayerBundle {
-    player: Player,
+pub struct BodyBundle {
+    run_scoped: RunScoped,
     health: Health,
     dmg: Dmg,
+    impact_threshold: ImpactThreshold,
+    throw_charge: ThrowCharge,
     storage: Storage,
     blueprint: Blueprint,
 
@@ -26,33 +35,122 @@ pub struct PlayerBundle {
     #[bundle]
     collider: ColliderBundle,
     sync: RigidBodyPositionSync,
+    interpolated: Interpolated,
+}
+
+impl BodyBundle {
+    pub fn new(sprite_atlas_handle: &SpriteAtlasHandle, pos: Vec2, hp: i32, color: Color) -> Self {
+        BodyBundle {
+            run_scoped: RunScoped,
+            health: Health::new(hp),
+            dmg: Dmg(1),
+            impact_threshold: ImpactThreshold(DEFAULT_IMPACT_THRESHOLD),
+            throw_charge: ThrowCharge::default(),
+            storage: Storage {
+                items: vec![Type::Empty; STORAGE_SIZE],
+            },
+            blueprint: Blueprint {
+                items: vec![Type::Empty; BLUEPRINT_SIZE],
+            },
+            sprite: SpriteSheetBundle {
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, 5.0),
+                    scale: Vec3::new(0.8, 0.8, 1.0),
+                    ..Default::default()
+                },
+                sprite: TextureAtlasSprite {
+                    color,
+                    ..TextureAtlasSprite::new(
+                        sprite_atlas_handle.index("sprites/player/body-line.png"),
+                    )
+                },
+                texture_atlas: sprite_atlas_handle.handle.clone(),
+                ..Default::default()
+            },
+            rigid_body: RigidBodyBundle {
+                position: pos.into(),
+                ..Default::default()
+            },
+            collider: ColliderBundle {
+                shape: ColliderShape::cuboid(2.0, 2.0).into(),
+                // mass_properties: ColliderMassProps::Density(1.0).into(),
+                flags: (ActiveEvents::CONTACT_EVENTS | ActiveEvents::INTERSECTION_EVENTS).into(),
+                ..Default::default()
+            },
+            sync: RigidBodyPositionSync::Discrete,
+            interpolated: Interpolated::default(),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct PlayerBundle {
+    player: Player,
+    #[bundle]
+    body: BodyBundle,
+}
+
+#[derive(Bundle)]
+pub struct EnemyBundle {
+    enemy: Enemy,
+    ai: AiController,
+    #[bundle]
+    body: BodyBundle,
 }
 
 #[derive(Bundle)]
 pub struct ObjectBundle {
     pub object: Object,
+    pub run_scoped: RunScoped,
     pub throwable: Throwable,
 
     pub health: Health,
     pub dmg: Dmg,
+    pub impact_threshold: ImpactThreshold,
 
-    #[bundle]
-    pub sprite: SpriteSheetBundle,
     #[bundle]
     pub rigid_body: RigidBodyBundle,
     #[bundle]
     pub collider: ColliderBundle,
     pub sync: RigidBodyPositionSync,
+    pub interpolated: Interpolated,
+}
+
+impl ObjectBundle {
+    /// The visual is inserted separately by `ObjectDef::insert_visual`.
+    pub fn new(def: &ObjectDef, id: Type, pos: Vec2) -> Self {
+        ObjectBundle {
+            object: Object {},
+            run_scoped: RunScoped,
+            throwable: Throwable(id),
+            health: Health::new(def.health),
+            dmg: Dmg(def.dmg),
+            impact_threshold: ImpactThreshold(def.impact_threshold),
+            rigid_body: RigidBodyBundle {
+                position: (pos, 0.0).into(),
+                ..Default::default()
+            },
+            collider: ColliderBundle {
+                shape: def.collider.shape().into(),
+                mass_properties: ColliderMassProps::Density(def.density).into(),
+                flags: (ActiveEvents::CONTACT_EVENTS | ActiveEvents::INTERSECTION_EVENTS).into(),
+                ..Default::default()
+            },
+            sync: RigidBodyPositionSync::Discrete,
+            interpolated: Interpolated::default(),
+        }
+    }
 }
 
 impl Default for ObjectBundle {
     fn default() -> Self {
         ObjectBundle {
             object: Object {},
+            run_scoped: RunScoped,
             throwable: Throwable(Type::Empty),
             health: Health { hp: 2 },
             dmg: Dmg(1),
-            sprite: SpriteSheetBundle::default(),
+            impact_threshold: ImpactThreshold(0.0),
             rigid_body: RigidBodyBundle {
                 ..Default::default()
             },
@@ -60,6 +158,7 @@ impl Default for ObjectBundle {
                 ..Default::default()
             },
             sync: RigidBodyPositionSync::Discrete,
+            interpolated: Interpolated::default(),
         }
     }
 }
@@ -72,6 +171,13 @@ pub trait CommandsSpawner<'w, 's> {
         y: f32,
     ) -> EntityCommands<'w, 's, 'a>;
 
+    fn spawn_enemy<'a>(
+        &'a mut self,
+        sprite_atlas_handle: &SpriteAtlasHandle,
+        x: f32,
+        y: f32,
+    ) -> EntityCommands<'w, 's, 'a>;
+
     fn spawn_sprite<'a>(
         &'a mut self,
         sprite_atlas_handle: &SpriteAtlasHandle,
@@ -81,6 +187,7 @@ pub trait CommandsSpawner<'w, 's> {
 
     fn spawn_object<'a>(
         &'a mut self,
+        registry: &ObjectRegistry,
         sprite_atlas_handle: &SpriteAtlasHandle,
         id: Type,
         pos: [f32; 2],
@@ -97,37 +204,7 @@ impl<'w, 's> CommandsSpawner<'w, 's> for Commands<'w, 's> {
         let mut e = self.spawn();
         e.insert_bundle(PlayerBundle {
             player: Player {},
-            health: Health { hp: 100 },
-            dmg: Dmg(1),
-            storage: Storage {
-                items: vec![Type::Empty; STORAGE_SIZE],
-            },
-            blueprint: Blueprint {
-                items: vec![Type::Empty; BLUEPRINT_SIZE],
-            },
-            sprite: SpriteSheetBundle {
-                transform: Transform {
-                    translation: Vec3::new(0.0, 0.0, 5.0),
-                    scale: Vec3::new(0.8, 0.8, 1.0),
-                    ..Default::default()
-                },
-                sprite: TextureAtlasSprite::new(
-                    sprite_atlas_handle.map[&"sprites/player/body-line.png".into()],
-                ),
-                texture_atlas: sprite_atlas_handle.handle.clone(),
-                ..Default::default()
-            },
-            rigid_body: RigidBodyBundle {
-                position: Vec2::new(x, y).into(),
-                ..Default::default()
-            },
-            collider: ColliderBundle {
-                shape: ColliderShape::cuboid(2.0, 2.0).into(),
-                // mass_properties: ColliderMassProps::Density(1.0).into(),
-                flags: (ActiveEvents::CONTACT_EVENTS | ActiveEvents::INTERSECTION_EVENTS).into(),
-                ..Default::default()
-            },
-            sync: RigidBodyPositionSync::Discrete,
+            body: BodyBundle::new(sprite_atlas_handle, Vec2::new(x, y), PLAYER_HP, Color::WHITE),
         })
         .with_children(|parent| {
             parent.spawn_bundle(SpriteSheetBundle {
@@ -136,7 +213,7 @@ impl<'w, 's> CommandsSpawner<'w, 's> for Commands<'w, 's> {
                     ..Default::default()
                 },
                 sprite: TextureAtlasSprite::new(
-                    sprite_atlas_handle.map[&"sprites/player/body-shadow.png".into()],
+                    sprite_atlas_handle.index("sprites/player/body-shadow.png"),
                 ),
                 texture_atlas: sprite_atlas_handle.handle.clone(),
                 ..Default::default()
@@ -145,6 +222,21 @@ impl<'w, 's> CommandsSpawner<'w, 's> for Commands<'w, 's> {
         e
     }
 
+    fn spawn_enemy<'a>(
+        &'a mut self,
+        sprite_atlas_handle: &SpriteAtlasHandle,
+        x: f32,
+        y: f32,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut e = self.spawn();
+        e.insert_bundle(EnemyBundle {
+            enemy: Enemy,
+            ai: AiController::default(),
+            body: BodyBundle::new(sprite_atlas_handle, Vec2::new(x, y), ENEMY_HP, ENEMY_COLOR),
+        });
+        e
+    }
+
     fn spawn_sprite<'a>(
         &'a mut self,
         sprite_atlas_handle: &SpriteAtlasHandle,
@@ -176,54 +268,25 @@ impl<'w, 's> CommandsSpawner<'w, 's> for Commands<'w, 's> {
 
     fn spawn_object<'a>(
         &'a mut self,
+        registry: &ObjectRegistry,
         sprite_atlas_handle: &SpriteAtlasHandle,
         id: Type,
         pos: [f32; 2],
     ) -> EntityCommands<'w, 's, 'a> {
+        let def = registry.get(id);
         let mut e = self.spawn();
-        e.insert_bundle(OBJECTS[id as usize](sprite_atlas_handle, Vec2::from(pos)))
-            .with_children(|parent| {
-                parent.spawn_bundle(SpriteSheetBundle {
-                    // TODO: color
-                    transform: Transform {
-                        translation: Vec3::new(0.0, -4.0, 6.0),
-                        ..Default::default()
-                    },
-                    sprite: TextureAtlasSprite::new(
-                        sprite_atlas_handle.map[&"sprites/circle/shadow.png".into()],
-                    ),
-                    texture_atlas: sprite_atlas_handle.handle.clone(),
-                    ..Default::default()
-                });
-            });
-        match id {
-            Type::Heart => {
-                e.insert(Heal::new(1, 0.1))
-                    .insert(FreezeSource::new(0.97, 2.0));
-            }
-            Type::Square => {
-                e.insert(ParalyzeSource::new(1.0));
-            }
-            Type::Circle => {
-                e.insert(Sight::new(1.5)).insert(Explode::new(20.0, 20));
-            }
-            Type::Triangle => {
-                e.insert(BurnSource::new(1, 5.0, 0.5));
-            }
-            _ => {}
+        e.insert_bundle(ObjectBundle::new(def, id, Vec2::from(pos)));
+        def.insert_visual(&mut e, sprite_atlas_handle, Usage::World);
+        for magic in def.magic.iter() {
+            magic.insert(&mut e);
+        }
+        if !def.resistances.is_empty() {
+            e.insert(Resistances(def.resistances.iter().cloned().collect()));
         }
         e
     }
 }
 
-/// object!(id: Type, pos: [f32, f32])
-#[macro_export]
-macro_rules! object {
-    ($id:expr, $pos:expr) => {
-        OBJECTS[$id as usize](Vec2::from($pos))
-    };
-}
-
 #[derive(Component)]
 pub struct Undead;
 
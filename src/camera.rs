@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use bevy_rapier2d::prelude::*;
+use bevy::render::camera::CameraProjection;
 
 use crate::component::Player;
 use crate::AppState;
@@ -7,6 +7,8 @@ use crate::RAPIER_TO_BEVY;
 
 const PLAYER_VIEW_WIDTH: f32 = 1920.0;
 const PLAYER_VIEW_HEIGHT: f32 = 1080.0;
+/// Share of the distance to the player the camera catches up with in a 60th of a second.
+const CAMERA_FOLLOW: f32 = 0.5;
 
 pub struct CameraPlugin;
 
@@ -17,8 +19,7 @@ impl Plugin for CameraPlugin {
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
                     .with_system(move_camera)
-                    .label("camera")
-                    .before("general"),
+                    .label("camera"),
             );
     }
 }
@@ -41,20 +42,38 @@ pub fn setup_camera(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
 }
 
+/// Position of the cursor in rapier units, as seen through the camera.
+/// Use the `GlobalTransform` of the camera: it is where the last frame was drawn from.
+pub fn cursor_to_world(
+    window: &Window,
+    camera_transform: &GlobalTransform,
+    projection: &OrthographicProjection,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    let size = Vec2::new(window.width(), window.height());
+    let ndc = cursor / size * 2.0 - Vec2::ONE;
+    let ndc_to_world =
+        camera_transform.compute_matrix() * projection.get_projection_matrix().inverse();
+    let world = ndc_to_world.project_point3(ndc.extend(-1.0));
+    Some(world.truncate() / RAPIER_TO_BEVY)
+}
+
+/// Follows the drawn player, which is interpolated between steps, see `fixed_step`.
 pub fn move_camera(
-    player: Query<&RigidBodyPositionComponent, With<Player>>,
+    time: Res<Time>,
+    player: Query<&Transform, (With<Player>, Without<MainCamera>)>,
     mut camera: Query<(&mut Transform, &MainCamera)>,
 ) {
-    let player_pos = player.single();
-    let player_translation = &player_pos.position.translation;
+    let player_translation = player.single().translation;
     let (mut camera_transform, _main_camera): (Mut<Transform>, &MainCamera) = camera.single_mut();
     let camera_translation = camera_transform.translation;
     let dir = Vec2::new(
-        player_translation.x * RAPIER_TO_BEVY - camera_translation.x,
-        player_translation.y * RAPIER_TO_BEVY - camera_translation.y,
+        player_translation.x - camera_translation.x,
+        player_translation.y - camera_translation.y,
     );
-    camera_transform.translation.x += dir.x * 0.5;
-    camera_transform.translation.y += dir.y * 0.5;
+    let follow = 1.0 - (1.0 - CAMERA_FOLLOW).powf(time.delta_seconds() * 60.0);
+    camera_transform.translation.x += dir.x * follow;
+    camera_transform.translation.y += dir.y * follow;
     // camera_transform.translation.x = player_pos.x * RAPIER_TO_BEVY;
     // camera_transform.translation.y = player_pos.y * RAPIER_TO_BEVY;
 }
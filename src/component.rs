@@ -2,19 +2,57 @@ use crate::shape_mod::Type;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 #[derive(Component)]
 pub struct Player;
 
+/// Fights the player, driven by `enemy::AiController`.
+#[derive(Component)]
+pub struct Enemy;
+
 #[derive(Component)]
 pub struct Object;
 
+/// Belongs to the current run and is despawned when it ends.
+#[derive(Component)]
+pub struct RunScoped;
+
 #[derive(Component)]
 pub struct Throwable(pub Type);
 
 #[derive(Component, Debug)]
 pub struct Grabbed(pub Entity);
 
+/// Whatever this entity destroys counts for the player: the player, objects the player has
+/// held, thrown ones included, and what their explosions set off.
+#[derive(Component)]
+pub struct PlayerCredit;
+
+/// Seconds to charge a throw to full power.
+pub const THROW_CHARGE_TIME: f32 = 1.0;
+/// Power of a throw released right away.
+pub const THROW_MIN_POWER: f32 = 0.2;
+
+/// How long the throw key has been held, if a throw is being charged.
+#[derive(Component, Default, Debug)]
+pub struct ThrowCharge {
+    pub held: Option<f32>,
+}
+
+impl ThrowCharge {
+    /// Power of the throw in `THROW_MIN_POWER..=1.0`, if charging.
+    pub fn power(&self) -> Option<f32> {
+        self.held.map(|held| {
+            THROW_MIN_POWER + (1.0 - THROW_MIN_POWER) * (held / THROW_CHARGE_TIME).min(1.0)
+        })
+    }
+}
+
+/// Bar near the player showing the power of the throw being charged.
+#[derive(Component)]
+pub struct ChargeIndicator;
+
 #[derive(Component)]
 pub struct Health {
     pub hp: i32,
@@ -74,10 +112,12 @@ pub struct Blueprint {
 }
 
 impl From<Blueprint> for std::vec::Vec<(Type, usize)> {
-    fn from(mut bp: Blueprint) -> Self {
-        bp.items.sort_by(|a, b| b.cmp(a));
+    fn from(bp: Blueprint) -> Self {
         let m = bp.items.into_iter().filter(|x| *x != Type::Empty).counts();
-        m.into_iter().collect()
+        // sorted so the key matches the recipe table regardless of hash order
+        let mut v: std::vec::Vec<(Type, usize)> = m.into_iter().collect();
+        v.sort();
+        v
     }
 }
 
@@ -115,3 +155,35 @@ pub struct StorageInHand {
 
 #[derive(Component)]
 pub struct EndGameUI;
+
+#[derive(Component)]
+pub struct PauseUI;
+
+#[derive(Component)]
+pub struct MainMenuUI;
+
+#[derive(Component)]
+pub struct SettingsUI;
+
+/// Chosen on the main menu before starting a run.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Arena,
+    /// The player takes no damage.
+    Practice,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Arena
+    }
+}
+
+impl GameMode {
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Arena => GameMode::Practice,
+            GameMode::Practice => GameMode::Arena,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::component::{GameMode, Health, Player};
+use crate::fixed_step::AddStepSystems;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_step_system_set(
+                SystemSet::new()
+                    .label("apply_damage")
+                    .with_system(apply_damage_system)
+                    .after("collision_detection")
+                    .before("despawn_dead_entities"),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DamageKind {
+    Impact,
+    Burn,
+    Explosion,
+    Reaction,
+}
+
+/// Every loss of hp goes through this event. `source` is the entity that caused it, if any.
+#[derive(Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub amount: i32,
+    pub kind: DamageKind,
+}
+
+/// Sent by `apply_damage_system` for the hit that takes the target's hp to 0,
+/// with the source of that hit.
+#[derive(Clone, Copy, Debug)]
+pub struct DeathEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+}
+
+/// Contact impulse, in rapier units, worth one more multiple of the hitter's `Dmg`.
+pub const IMPULSE_PER_DMG: f32 = 200.0;
+
+/// Impacts below this contact impulse don't hurt the holder.
+/// Entities without one are hurt by any impact.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ImpactThreshold(pub f32);
+
+/// Threshold of the player and enemies, and of objects whose definition doesn't set one.
+pub const DEFAULT_IMPACT_THRESHOLD: f32 = 400.0;
+
+/// Impulse needed to stop two bodies moving apart at `rel_speed`, i.e. their reduced mass
+/// times `rel_speed`. Pass an inverse mass of 0 for bodies that can't move.
+pub fn impact_impulse(inv_mass1: f32, inv_mass2: f32, rel_speed: f32) -> f32 {
+    let inv_mass = inv_mass1 + inv_mass2;
+    if inv_mass > 0.0 {
+        rel_speed / inv_mass
+    } else {
+        0.0
+    }
+}
+
+/// Damage taken from an impact of `impulse` by an entity dealing `dmg`.
+/// Crossing `threshold` deals `dmg`, and every `IMPULSE_PER_DMG` above it deals `dmg` again.
+pub fn impact_damage(dmg: i32, impulse: f32, threshold: f32) -> i32 {
+    if impulse <= threshold {
+        0
+    } else {
+        dmg * (1 + ((impulse - threshold) / IMPULSE_PER_DMG) as i32)
+    }
+}
+
+/// Fraction of each kind of damage the holder ignores. Negative values take extra damage.
+#[derive(Component, Default, Debug)]
+pub struct Resistances(pub HashMap<DamageKind, f32>);
+
+impl Resistances {
+    pub fn reduce(&self, kind: DamageKind, amount: i32) -> i32 {
+        match self.0.get(&kind) {
+            Some(resistance) => (amount as f32 * (1.0 - resistance).max(0.0)).round() as i32,
+            None => amount,
+        }
+    }
+}
+
+impl DamageEvent {
+    /// Damage the target takes from this event.
+    pub fn resisted(&self, resistances: Option<&Resistances>) -> i32 {
+        match resistances {
+            Some(resistances) => resistances.reduce(self.kind, self.amount),
+            None => self.amount,
+        }
+    }
+}
+
+/// The only system that takes hp away.
+/// Events for entities despawned in the meantime are dropped.
+fn apply_damage_system(
+    mode: Res<GameMode>,
+    mut ev_damage: EventReader<DamageEvent>,
+    mut ev_death: EventWriter<DeathEvent>,
+    mut health_query: Query<(&mut Health, Option<&Resistances>, Option<&Player>)>,
+) {
+    for ev in ev_damage.iter() {
+        if let Ok((mut health, resistances, player)) = health_query.get_mut(ev.target) {
+            if player.is_some() && *mode == GameMode::Practice {
+                continue;
+            }
+            let was_alive = health.hp > 0;
+            health.hp -= ev.resisted(resistances);
+            if was_alive && health.hp <= 0 {
+                ev_death.send(DeathEvent {
+                    target: ev.target,
+                    source: ev.source,
+                });
+            }
+        }
+    }
+}
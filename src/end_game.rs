@@ -1,26 +1,94 @@
 use bevy::prelude::*;
 
 use super::AppState;
+use crate::action::{clear_action_edges, Action, ActionState};
 use crate::component::*;
+use crate::rng::GameRng;
+use crate::score::{HighScores, Score};
+use crate::stats::{Hit, RunStats};
+use std::collections::BTreeMap;
 
 pub struct EndGamePlugin;
 
 impl Plugin for EndGamePlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
-            SystemSet::on_enter(AppState::EndGame).with_system(load_end_game_display),
+            SystemSet::on_enter(AppState::EndGame)
+                .with_system(load_end_game_display.after("record_score"))
+                .with_system(clear_action_edges),
         )
         .add_system_set(SystemSet::on_update(AppState::EndGame).with_system(end_game_input_system))
         .add_system_set(SystemSet::on_exit(AppState::EndGame).with_system(despawn_end_game_ui));
     }
 }
 
-fn load_end_game_display(mut commands: Commands, asset_server: ResMut<AssetServer>) {
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// "Impact 12, Burn 3", or "-" if `counts` is empty.
+fn join_counts<K: std::fmt::Debug, V: std::fmt::Display>(counts: &BTreeMap<K, V>) -> String {
+    if counts.is_empty() {
+        return "-".to_owned();
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(key, count)| format!("{:?} {}", key, count))
+        .collect();
+    counts.join(", ")
+}
+
+fn stat_lines(stats: &RunStats) -> Vec<String> {
+    let mut lines = vec![
+        format!("damage dealt  {}", join_counts(&stats.damage_dealt)),
+        format!("damage taken  {}", join_counts(&stats.damage_taken)),
+        format!("thrown {}  stored {}", stats.thrown, stats.stored),
+        format!("synthesized   {}", join_counts(&stats.syntheses)),
+        format!(
+            "paralyzed {}  frozen {}",
+            format_time(stats.paralyzed_time),
+            format_time(stats.frozen_time)
+        ),
+    ];
+    match stats.cause_of_death {
+        Some(Hit {
+            kind,
+            source: Some(source),
+        }) => lines.push(format!("killed by {:?} ({:?})", kind, source)),
+        Some(Hit { kind, source: None }) => lines.push(format!("killed by {:?}", kind)),
+        None => {}
+    }
+    lines
+}
+
+fn load_end_game_display(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    stats: Res<RunStats>,
+    rng: Res<GameRng>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let mono: Handle<Font> = asset_server.load("fonts/FiraMono-Medium.ttf");
+    let text = |value: String, font: &Handle<Font>, font_size: f32, color: Color| TextBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    };
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                padding: Rect::all(Val::Percent(25.0)),
+                padding: Rect::all(Val::Percent(5.0)),
                 position_type: PositionType::Absolute,
                 flex_direction: FlexDirection::ColumnReverse,
                 align_items: AlignItems::Center,
@@ -31,26 +99,75 @@ fn load_end_game_display(mut commands: Commands, asset_server: ResMut<AssetServe
         })
         .insert(EndGameUI {})
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "(press enter to continue)",
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                        font_size: 40.0,
-                        color: Color::ANTIQUE_WHITE,
-                    },
-                    Default::default(),
+            let white = Color::ANTIQUE_WHITE;
+            parent.spawn_bundle(text(format!("Score: {}", score.total()), &font, 60.0, white));
+            parent.spawn_bundle(text(
+                format!(
+                    "destroyed {}  synthesized {}  survived {}",
+                    score.destroyed,
+                    score.synthesized,
+                    format_time(score.time)
                 ),
+                &font,
+                30.0,
+                white,
+            ));
+            let column = || NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::FlexStart,
+                    margin: Rect::all(Val::Px(20.0)),
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
                 ..Default::default()
-            });
+            };
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(column()).with_children(|parent| {
+                        parent.spawn_bundle(text("High scores".to_owned(), &font, 40.0, white));
+                        for (rank, entry) in high_scores.entries.iter().enumerate() {
+                            let color = if high_scores.last == Some(rank) {
+                                Color::GOLD
+                            } else {
+                                white
+                            };
+                            let line = format!(
+                                "{:>2}. {:>6}  {:>5}",
+                                rank + 1,
+                                entry.total,
+                                format_time(entry.score.time)
+                            );
+                            parent.spawn_bundle(text(line, &mono, 26.0, color));
+                        }
+                    });
+                    parent.spawn_bundle(column()).with_children(|parent| {
+                        parent.spawn_bundle(text("This run".to_owned(), &font, 40.0, white));
+                        for line in stat_lines(&stats) {
+                            parent.spawn_bundle(text(line, &mono, 26.0, white));
+                        }
+                        let seed = format!("seed {}", rng.run_seed);
+                        parent.spawn_bundle(text(seed, &mono, 26.0, white));
+                    });
+                });
+            parent.spawn_bundle(text("(press enter to continue)".to_owned(), &font, 40.0, white));
         });
 }
 
 fn end_game_input_system(
     mut app_state: ResMut<State<AppState>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    if keyboard_input.pressed(KeyCode::Return) {
+    if actions.just_pressed(Action::Confirm) {
         let _ = app_state.set(AppState::InGame);
     }
 }
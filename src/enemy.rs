@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_rapier2d::physics::RigidBodyComponentsQueryPayload;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+
+use super::AppState;
+use crate::bundle::CommandsSpawner;
+use crate::component::*;
+use crate::fixed_step::{AddStepSystems, SimTime};
+use crate::in_game::{drive, grab_object, throw_impulse, turn_towards};
+use crate::magic::Paralyzed;
+use crate::rng::GameRng;
+use crate::SpriteAtlasHandle;
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemySpawnTimer(Timer::from_seconds(
+            ENEMY_SPAWN_INTERVAL,
+            true,
+        )))
+        .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_enemy_spawns))
+        .add_step_system_set(
+            SystemSet::new()
+                .label("enemy")
+                .after("detection")
+                .before("apply_damage")
+                .with_system(spawn_enemies)
+                .with_system(enemy_ai_system.label("enemy_ai"))
+                .with_system(enemy_throw_system.after("enemy_ai")),
+        );
+    }
+}
+
+pub const ENEMY_HP: i32 = 30;
+pub const ENEMY_COLOR: Color = Color::rgb(1.0, 0.4, 0.4);
+/// Seconds between two enemies entering the arena.
+const ENEMY_SPAWN_INTERVAL: f32 = 15.0;
+const MAX_ENEMIES: usize = 3;
+/// Where enemies enter the arena: the corner farthest from the player.
+const ENEMY_SPAWNS: [(f32, f32); 4] =
+    [(-80.0, 40.0), (80.0, 40.0), (-80.0, -40.0), (80.0, -40.0)];
+/// Force pushing an enemy along its movement, a bit less than the player's.
+const ENEMY_FORCE: f32 = 2000.0;
+/// Seconds an enemy keeps wandering in the same direction.
+const WANDER_TIME: f32 = 2.0;
+/// Free objects within this distance are fetched, in rapier units.
+const SIGHT_RANGE: f32 = 60.0;
+/// An object this close and in front of an enemy is grabbed, as the player's ray would.
+const GRAB_RANGE: f32 = 7.0;
+/// An enemy holding an object closes in to this distance of the player before throwing.
+const THROW_RANGE: f32 = 40.0;
+/// How far off the target an enemy may face to grab or throw, in radians.
+const AIM_TOLERANCE: f32 = 0.2;
+/// Seconds an enemy charges a throw.
+const ENEMY_CHARGE_TIME: f32 = 0.6;
+
+/// Seconds until the next enemy enters the arena.
+struct EnemySpawnTimer(Timer);
+
+fn reset_enemy_spawns(mut timer: ResMut<EnemySpawnTimer>) {
+    timer.0.reset();
+}
+
+fn spawn_enemies(
+    mut commands: Commands,
+    time: Res<SimTime>,
+    sprite_atlas_handle: Res<SpriteAtlasHandle>,
+    mut timer: ResMut<EnemySpawnTimer>,
+    player_query: Query<&RigidBodyPositionComponent, With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() || enemy_query.iter().len() >= MAX_ENEMIES {
+        return;
+    }
+    let player = match player_query.get_single() {
+        Ok(player_pos) => Vec2::from(player_pos.position.translation.vector),
+        Err(_) => return,
+    };
+    let farthest = ENEMY_SPAWNS
+        .iter()
+        .map(|&(x, y)| Vec2::new(x, y))
+        .max_by(|a, b| {
+            let (a, b) = (a.distance_squared(player), b.distance_squared(player));
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or_default();
+    commands.spawn_enemy(&sprite_atlas_handle, farthest.x, farthest.y);
+}
+
+/// Drives an enemy the way input drives the player: it wanders, fetches the nearest free
+/// object, then faces the player and throws it.
+#[derive(Component, Default, Debug)]
+pub struct AiController {
+    /// The object held, as `EntityInHand` for the player.
+    pub held: Option<Entity>,
+    wander: Vec2,
+    /// Seconds until the next wandering direction.
+    wander_time: f32,
+}
+
+#[allow(clippy::type_complexity)]
+fn enemy_ai_system(
+    mut commands: Commands,
+    time: Res<SimTime>,
+    mut rng: ResMut<GameRng>,
+    player_query: Query<&RigidBodyPositionComponent, With<Player>>,
+    object_query: Query<(Entity, &RigidBodyPositionComponent), (With<Throwable>, Without<Grabbed>)>,
+    grabbed_query: Query<&Grabbed>,
+    mut enemy_query: Query<
+        (
+            Entity,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &mut RigidBodyForcesComponent,
+            &mut ThrowCharge,
+            &mut AiController,
+        ),
+        (With<Enemy>, Without<Paralyzed>),
+    >,
+) {
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|player_pos| Vec2::from(player_pos.position.translation.vector));
+    // objects grabbed this step, so two enemies don't grab the same one
+    let mut claimed = HashSet::default();
+    for (e, pos, mut vel, mut forces, mut charge, mut ai) in enemy_query.iter_mut() {
+        let here = Vec2::from(pos.position.translation.vector);
+        // the object was destroyed in the meantime
+        if let Some(held) = ai.held {
+            if grabbed_query.get(held).map_or(true, |grabbed| grabbed.0 != e) {
+                ai.held = None;
+                charge.held = None;
+            }
+        }
+        let nearest = object_query
+            .iter()
+            .filter(|(object, _)| !claimed.contains(object))
+            .map(|(object, object_pos)| {
+                (object, Vec2::from(object_pos.position.translation.vector))
+            })
+            .filter(|(_, there)| there.distance(here) <= SIGHT_RANGE)
+            .min_by(|(_, a), (_, b)| {
+                let (a, b) = (a.distance_squared(here), b.distance_squared(here));
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            });
+        let movement = match (ai.held, player, nearest) {
+            (Some(_), Some(player), _) => {
+                let to_player = player - here;
+                let off = turn_towards(pos, &mut vel, to_player);
+                let in_range = to_player.length() <= THROW_RANGE;
+                if in_range && off.abs() <= AIM_TOLERANCE {
+                    *charge.held.get_or_insert(0.0) += time.delta_seconds();
+                }
+                if in_range {
+                    Vec2::ZERO
+                } else {
+                    to_player.normalize_or_zero()
+                }
+            }
+            (None, _, Some((object, there))) => {
+                let to_object = there - here;
+                let off = turn_towards(pos, &mut vel, to_object);
+                if to_object.length() <= GRAB_RANGE && off.abs() <= AIM_TOLERANCE {
+                    grab_object(&mut commands, e, object, [4.0, 7.0], false);
+                    claimed.insert(object);
+                    ai.held = Some(object);
+                    Vec2::ZERO
+                } else {
+                    to_object.normalize_or_zero()
+                }
+            }
+            _ => {
+                ai.wander_time -= time.delta_seconds();
+                if ai.wander_time <= 0.0 {
+                    let angle = rng.gameplay.gen_range(-PI..PI);
+                    ai.wander = Vec2::new(angle.cos(), angle.sin());
+                    ai.wander_time = WANDER_TIME;
+                }
+                turn_towards(pos, &mut vel, ai.wander);
+                ai.wander
+            }
+        };
+        drive(&mut vel, &mut forces, movement, ENEMY_FORCE);
+    }
+}
+
+/// Throws what a fully charged enemy holds, along the same impulse as the player's throws.
+fn enemy_throw_system(
+    mut commands: Commands,
+    mut joint_set: ResMut<ImpulseJointSet>,
+    mut island_manager: ResMut<IslandManager>,
+    mut q: QuerySet<(
+        QueryState<(Entity, &RigidBodyPositionComponent, &mut ThrowCharge, &mut AiController)>,
+        QueryState<
+            (
+                &mut RigidBodyVelocityComponent,
+                &RigidBodyMassPropsComponent,
+            ),
+            With<Throwable>,
+        >,
+        QueryState<RigidBodyComponentsQueryPayload>,
+    )>,
+) {
+    let mut throws = vec![];
+    for (e, pos, mut charge, mut ai) in q.q0().iter_mut() {
+        if charge.held.map_or(true, |held| held < ENEMY_CHARGE_TIME) {
+            continue;
+        }
+        if let (Some(power), Some(object)) = (charge.power(), ai.held) {
+            throws.push((e, object, pos.position.rotation, power));
+        }
+        charge.held = None;
+        ai.held = None;
+    }
+    if throws.is_empty() {
+        return;
+    }
+    let mut object_query = q.q1();
+    for (_, object, rot, power) in throws.iter() {
+        if let Ok((mut vel, mprops)) = object_query.get_mut(*object) {
+            throw_impulse(&mut vel, mprops, *rot, *power);
+        }
+    }
+    let mut rigid_body_set = RigidBodyComponentsSet(q.q2());
+    for (e, object, _, _) in throws {
+        joint_set.remove_joints_attached_to_rigid_body(
+            e.handle(),
+            &mut island_manager,
+            &mut rigid_body_set,
+        );
+        commands.entity(object).remove::<Grabbed>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape_mod::Type;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn enemy_grabs_the_object_in_front_of_it() {
+        let mut app = TestApp::new();
+        let enemy = app.spawn_enemy(Vec2::new(40.0, 20.0));
+        let object = app.spawn_object(Type::Triangle, [48.0, 20.0]);
+        app.steps(30);
+        assert_eq!(app.get::<AiController>(enemy).unwrap().held, Some(object));
+        assert_eq!(app.get::<Grabbed>(object).unwrap().0, enemy);
+    }
+
+    #[test]
+    fn enemy_throws_the_held_object_at_the_player() {
+        let mut app = TestApp::new();
+        let player = app.player();
+        let enemy = app.spawn_enemy(Vec2::new(30.0, 20.0));
+        let object = app.spawn_object(Type::Rect, [37.0, 20.0]);
+        assert!(app.step_until(60, |app| app.get::<Grabbed>(object).is_some()));
+        assert!(app.step_until(300, |app| app.get::<Grabbed>(object).is_none()));
+        assert!(app.get::<AiController>(enemy).unwrap().held.is_none());
+        let to_player = app.position(player) - app.position(object);
+        let vel = app.get::<RigidBodyVelocityComponent>(object).unwrap();
+        assert!(Vec2::from(vel.linvel).dot(to_player) > 0.0);
+    }
+
+    #[test]
+    fn dead_enemy_drops_the_held_object() {
+        let mut app = TestApp::new();
+        let enemy = app.spawn_enemy(Vec2::new(40.0, 20.0));
+        let object = app.spawn_object(Type::Rect, [47.0, 20.0]);
+        assert!(app.step_until(60, |app| app.get::<Grabbed>(object).is_some()));
+        app.damage(enemy, ENEMY_HP);
+        app.step();
+        assert!(app.app.world.get_entity(enemy).is_none());
+        assert!(app.app.world.get_entity(object).is_some());
+        assert!(app.get::<Grabbed>(object).is_none());
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_rapier2d::physics::{
+    attach_bodies_and_colliders_system, create_joints_system, step_world_system,
+};
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+use super::{AppState, RAPIER_TO_BEVY, TIME_STEP};
+use crate::action::{ActionState, StepActions};
+use crate::component::{Health, Player};
+
+pub struct FixedStepPlugin;
+
+impl Plugin for FixedStepPlugin {
+    fn build(&self, app: &mut App) {
+        let step = Schedule::default()
+            .with_run_criteria(fixed_step)
+            .with_stage(STEP_BEGIN, SystemStage::parallel())
+            .with_stage(STEP_UPDATE, SystemStage::parallel())
+            .with_stage(STEP_PHYSICS, SystemStage::parallel());
+        app.init_resource::<SimTime>()
+            .init_resource::<StepActions>()
+            .init_resource::<StepContacts>()
+            .add_stage_after(CoreStage::Update, FIXED_UPDATE, step)
+            .stage(FIXED_UPDATE, |step: &mut Schedule| {
+                step.add_system_set_to_stage(
+                    STEP_BEGIN,
+                    SystemSet::new()
+                        .with_system(begin_step.label("begin_step"))
+                        .with_system(attach_bodies_and_colliders_system)
+                        .with_system(create_joints_system)
+                        .with_system(store_previous_positions),
+                )
+                .add_system_set_to_stage(
+                    STEP_PHYSICS,
+                    // rapier's own `collect_removals` still runs once per frame, after the
+                    // frame's steps: running it in every step too would remove bodies twice
+                    SystemSet::new()
+                        .with_system(step_world_system::<NoUserData>.label("step_world"))
+                        .with_system(collect_contacts.after("step_world"))
+                        .with_system(end_step.after("step_world")),
+                )
+            })
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Stage running the simulation in steps of `TIME_STEP`, as many per frame as the time elapsed
+/// calls for. It sits between `CoreStage::Update` and rapier's writeback.
+pub const FIXED_UPDATE: &str = "fixed_update";
+/// Sub-stages of every step, in order: input and new rigid bodies, gameplay, then physics.
+pub const STEP_BEGIN: &str = "step_begin";
+const STEP_UPDATE: &str = "step_update";
+const STEP_PHYSICS: &str = "step_physics";
+
+/// A long frame catches up with at most this many steps, the rest of it is dropped.
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+pub trait AddStepSystems {
+    /// Run `system_set` in every step of the simulation. Labels are only seen by other step
+    /// systems, and state run criteria don't work there: steps only run during a run anyway.
+    fn add_step_system_set(&mut self, system_set: SystemSet) -> &mut Self;
+}
+
+impl AddStepSystems for App {
+    fn add_step_system_set(&mut self, system_set: SystemSet) -> &mut Self {
+        self.stage(FIXED_UPDATE, |step: &mut Schedule| {
+            step.add_system_set_to_stage(STEP_UPDATE, system_set)
+        })
+    }
+}
+
+/// Clock of the simulation. Gameplay reads this instead of `Time`: every step lasts `TIME_STEP`
+/// whatever the frame rate.
+#[derive(Default, Debug)]
+pub struct SimTime {
+    /// Real time not simulated yet, less than a step once the frame's steps are done.
+    accumulator: Duration,
+    /// Whether the frame's time was already added, as the steps of a frame run.
+    stepping: bool,
+    /// Run exactly one step per frame instead of following the clock.
+    every_frame: bool,
+}
+
+impl SimTime {
+    /// A clock for simulations that run as fast as possible, one step per update.
+    pub fn every_frame() -> Self {
+        SimTime {
+            every_frame: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn delta(&self) -> Duration {
+        Duration::from_secs_f32(TIME_STEP)
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        TIME_STEP
+    }
+
+    /// How far rendering is past the last step, as a fraction of a step.
+    pub fn overstep(&self) -> f32 {
+        self.accumulator.as_secs_f32() / TIME_STEP
+    }
+}
+
+/// Steps run while a run is on: in `InGame`, until the player dies. Time spent anywhere else,
+/// including the pause menu, isn't simulated later.
+fn fixed_step(
+    time: Res<Time>,
+    app_state: Res<State<AppState>>,
+    player_query: Query<&Health, With<Player>>,
+    mut sim_time: ResMut<SimTime>,
+    mut actions: ResMut<ActionState>,
+) -> ShouldRun {
+    let running = *app_state.current() == AppState::InGame
+        && player_query.get_single().map_or(false, |health| health.hp > 0);
+    if !running {
+        // a press that opened or left a menu isn't gameplay input
+        actions.take_step();
+        sim_time.stepping = false;
+        return ShouldRun::No;
+    }
+    if sim_time.every_frame {
+        return ShouldRun::Yes;
+    }
+    let step = sim_time.delta();
+    if !sim_time.stepping {
+        sim_time.accumulator =
+            (sim_time.accumulator + time.delta()).min(step * MAX_STEPS_PER_FRAME);
+    }
+    if sim_time.accumulator >= step {
+        sim_time.accumulator -= step;
+        sim_time.stepping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        sim_time.stepping = false;
+        ShouldRun::No
+    }
+}
+
+/// Hand the input since the last step to this step, and let rapier step with it.
+fn begin_step(
+    mut actions: ResMut<ActionState>,
+    mut step_actions: ResMut<StepActions>,
+    mut config: ResMut<RapierConfiguration>,
+) {
+    step_actions.0 = actions.take_step();
+    config.physics_pipeline_active = true;
+}
+
+/// Rapier's own step, once per frame, stays off: physics only advances with the steps.
+fn end_step(mut config: ResMut<RapierConfiguration>) {
+    config.physics_pipeline_active = false;
+}
+
+/// Contacts that started or stopped in the last step, for the gameplay of the next one.
+/// Rapier's events would be gone by then if a frame or two ran no step.
+#[derive(Default, Debug)]
+pub struct StepContacts(pub Vec<ContactEvent>);
+
+fn collect_contacts(mut events: EventReader<ContactEvent>, mut contacts: ResMut<StepContacts>) {
+    contacts.0 = events.iter().cloned().collect();
+}
+
+/// Position of a rigid body before the last step. Its `Transform` is drawn in between.
+#[derive(Component, Default, Debug)]
+pub struct Interpolated {
+    previous: Option<Isometry<Real>>,
+}
+
+fn store_previous_positions(mut query: Query<(&RigidBodyPositionComponent, &mut Interpolated)>) {
+    for (pos, mut interpolated) in query.iter_mut() {
+        interpolated.previous = Some(pos.position);
+    }
+}
+
+/// Runs after rapier's writeback. What is drawn is at most one step behind the simulation.
+fn interpolate_transforms(
+    sim_time: Res<SimTime>,
+    mut query: Query<(&RigidBodyPositionComponent, &Interpolated, &mut Transform)>,
+) {
+    let t = sim_time.overstep();
+    for (pos, interpolated, mut transform) in query.iter_mut() {
+        let position = match interpolated.previous {
+            Some(previous) => previous.lerp_slerp(&pos.position, t),
+            None => pos.position,
+        };
+        transform.translation.x = position.translation.x * RAPIER_TO_BEVY;
+        transform.translation.y = position.translation.y * RAPIER_TO_BEVY;
+        transform.rotation = Quat::from_rotation_z(position.rotation.angle());
+    }
+}
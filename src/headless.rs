@@ -0,0 +1,498 @@
+use bevy::asset::AssetPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+
+use super::{
+    arg_value, data_settled, setup_arena, AppState, DataHandles, SpriteAtlasHandle, TIME_STEP,
+};
+use crate::action::{Action, ActionState, StepActions};
+use crate::component::*;
+use crate::damage::{DamageEvent, Resistances};
+use crate::fixed_step::{AddStepSystems, FixedStepPlugin, SimTime, FIXED_UPDATE, STEP_BEGIN};
+use crate::in_game::{EntityInHand, InGamePlugin};
+use crate::object_def::ObjectDefPlugin;
+use crate::particle::ParticlePlugin;
+use crate::rng::{stream, GameRng, RngPlugin, BOT_STREAM};
+use crate::score::{HighScores, Score, ScorePlugin};
+use crate::shape_mod::{Type, BASIC};
+use crate::stats::{RunStats, StatsPlugin};
+use crate::synthesis::{Table, STORAGE_SIZE};
+
+/// `--headless 10` simulates 10 minutes of play without a window, as fast as possible, then
+/// prints balance metrics. Runs restart as soon as the player dies.
+pub const HEADLESS_FLAG: &str = "--headless";
+/// `--bot scripted` plays the headless simulation with `BotKind::Scripted`.
+pub const BOT_FLAG: &str = "--bot";
+
+/// Steps a scripted throw is charged for, which also leaves time to turn to the target.
+const THROW_CHARGE_STEPS: u32 = 30;
+/// Range of the steps a decision of the random bot lasts.
+const RANDOM_DECISION_STEPS: (u32, u32) = (10, 60);
+
+/// Which bot plays a headless simulation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BotKind {
+    /// Wanders, aims and presses actions at random.
+    Random,
+    /// Picks up the nearest object, then alternately stores and throws it, synthesizes whenever
+    /// the storage holds a recipe and throws what it synthesized.
+    Scripted,
+}
+
+impl BotKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(BotKind::Random),
+            "scripted" => Some(BotKind::Scripted),
+            _ => None,
+        }
+    }
+}
+
+/// Simulate the minutes given to `HEADLESS_FLAG` and print the metrics.
+pub fn run(minutes: &str) {
+    let minutes: f32 = match minutes.parse() {
+        Ok(minutes) if minutes > 0.0 => minutes,
+        _ => {
+            eprintln!("{} {}: expected a positive number of minutes", HEADLESS_FLAG, minutes);
+            std::process::exit(2);
+        }
+    };
+    let bot = match arg_value(BOT_FLAG) {
+        None => BotKind::Random,
+        Some(name) => match BotKind::parse(&name) {
+            Some(bot) => bot,
+            None => {
+                eprintln!("{} {}: expected random or scripted", BOT_FLAG, name);
+                std::process::exit(2);
+            }
+        },
+    };
+
+    let mut app = simulation_app();
+    app.add_plugin(LogPlugin::default())
+        .add_plugin(BotPlugin { bot });
+
+    let steps = (minutes * 60.0 / TIME_STEP).round() as u32;
+    while metrics(&app.world).steps < steps {
+        app.update();
+    }
+    print_report(&app.world, minutes, bot);
+}
+
+/// The gameplay plugins without a window, devices or menus: `Setup` leads straight into a run,
+/// and every update runs one simulation step. Also used by the tests, see `test_support`.
+pub fn simulation_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin::default())
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugin(FixedStepPlugin)
+        .add_plugin(RngPlugin)
+        .add_plugin(ObjectDefPlugin)
+        .add_plugin(ParticlePlugin)
+        .add_plugin(InGamePlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(SimulationPlugin)
+        .add_state(AppState::Setup);
+    app
+}
+
+/// Stands in for the windowed plugins. High scores are never saved.
+struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimTime::every_frame())
+            .insert_resource(HighScores::default())
+            .init_resource::<ActionState>()
+            .init_resource::<GameMode>()
+            .init_resource::<DataHandles>()
+            .init_resource::<SpriteAtlasHandle>()
+            .add_system_set(SystemSet::on_update(AppState::Setup).with_system(check_data))
+            .add_system_set(SystemSet::on_exit(AppState::Setup).with_system(setup_arena));
+    }
+}
+
+/// Plays the runs of a headless simulation and measures them. Runs restart on death.
+struct BotPlugin {
+    bot: BotKind,
+}
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bot::new(self.bot))
+            .init_resource::<Metrics>()
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_bot))
+            .add_system_set(SystemSet::on_update(AppState::EndGame).with_system(finish_run))
+            .stage(FIXED_UPDATE, |step: &mut Schedule| {
+                step.add_system_to_stage(STEP_BEGIN, bot_system.after("begin_step"))
+            })
+            .add_step_system_set(
+                SystemSet::new().with_system(
+                    damage_source_system
+                        .after("apply_damage")
+                        .before("despawn_dead_entities"),
+                ),
+            );
+    }
+}
+
+fn check_data(
+    mut app_state: ResMut<State<AppState>>,
+    data_handles: Res<DataHandles>,
+    asset_server: Res<AssetServer>,
+) {
+    if data_settled(&data_handles, &asset_server) {
+        let _ = app_state.set(AppState::InGame);
+    }
+}
+
+/// Everything the simulation measured, over all runs.
+#[derive(Default, Debug)]
+struct Metrics {
+    steps: u32,
+    /// Runs that ended with the player's death.
+    runs: Vec<(Score, RunStats)>,
+    /// Damage taken by the player after resistances, by the item that dealt it.
+    damage_taken_by_source: BTreeMap<String, i32>,
+}
+
+fn metrics(world: &World) -> &Metrics {
+    world
+        .get_resource::<Metrics>()
+        .expect("the bot plugin adds the metrics")
+}
+
+fn damage_source_system(
+    mut metrics: ResMut<Metrics>,
+    mut ev_damage: EventReader<DamageEvent>,
+    target_query: Query<Option<&Resistances>, With<Player>>,
+    source_query: Query<&Throwable>,
+) {
+    for ev in ev_damage.iter() {
+        if let Ok(resistances) = target_query.get(ev.target) {
+            let amount = ev.resisted(resistances);
+            if amount <= 0 {
+                continue;
+            }
+            let source = match ev.source.and_then(|source| source_query.get(source).ok()) {
+                Some(throwable) => format!("{:?}", throwable.0),
+                None => format!("{:?} without an item", ev.kind),
+            };
+            *metrics.damage_taken_by_source.entry(source).or_default() += amount;
+        }
+    }
+}
+
+/// The player died: keep the run and start the next one.
+fn finish_run(
+    mut app_state: ResMut<State<AppState>>,
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    mut metrics: ResMut<Metrics>,
+) {
+    metrics.runs.push((*score, stats.clone()));
+    let _ = app_state.set(AppState::InGame);
+}
+
+/// Input of the bot, fed to every step in place of the devices.
+struct Bot {
+    kind: BotKind,
+    /// Seeded from the run seed on the first step of a run, so a run is reproduced by its seed.
+    rng: Option<ChaCha8Rng>,
+    actions: ActionState,
+    /// Actions to tap, one every other step so each tap is a new press.
+    taps: VecDeque<Action>,
+    tapped: Option<Action>,
+    /// Steps left in the current decision of the random bot, or in a scripted throw.
+    wait: u32,
+    pickups: u32,
+}
+
+impl Bot {
+    fn new(kind: BotKind) -> Self {
+        Bot {
+            kind,
+            rng: None,
+            actions: ActionState::default(),
+            taps: VecDeque::new(),
+            tapped: None,
+            wait: 0,
+            pickups: 0,
+        }
+    }
+
+    /// Release the last tap or press the next one. Returns whether a tap took this step.
+    fn tap(&mut self) -> bool {
+        if let Some(action) = self.tapped.take() {
+            self.actions.set(action, false);
+            return true;
+        }
+        match self.taps.pop_front() {
+            Some(action) => {
+                self.actions.set(action, true);
+                self.tapped = Some(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn random_step(&mut self, player: Vec2, holding: bool) {
+        if self.wait > 0 {
+            self.wait -= 1;
+            return;
+        }
+        let rng = self.rng.as_mut().expect("the bot is seeded before its first step");
+        self.wait = rng.gen_range(RANDOM_DECISION_STEPS.0..=RANDOM_DECISION_STEPS.1);
+        self.actions.movement = if rng.gen_bool(0.2) {
+            Vec2::ZERO
+        } else {
+            random_direction(rng).normalize_or_zero()
+        };
+        self.actions.cursor = Some(player + random_direction(rng) * 10.0);
+        self.actions.set(Action::Grab, rng.gen_bool(0.5));
+        // a throw charges for a whole decision
+        let throw = !self.actions.pressed(Action::Throw) && holding && rng.gen_bool(0.5);
+        self.actions.set(Action::Throw, throw);
+        if rng.gen_bool(0.3) {
+            let taps = [
+                Action::Store,
+                Action::Hold,
+                Action::Synthesize,
+                Action::Clear,
+                Action::AddToBlueprint,
+                Action::NextSlot,
+                Action::Slot(rng.gen_range(0..STORAGE_SIZE)),
+            ];
+            self.taps.push_back(taps[rng.gen_range(0..taps.len())]);
+        }
+    }
+
+    fn scripted_step(
+        &mut self,
+        player: Vec2,
+        holding: bool,
+        storage: &Storage,
+        table: &Table,
+        nearest: Option<Vec2>,
+    ) {
+        if self.actions.pressed(Action::Throw) {
+            if self.wait > 0 {
+                self.wait -= 1;
+            } else {
+                self.actions.set(Action::Throw, false);
+            }
+            return;
+        }
+        self.actions.movement = Vec2::ZERO;
+        if holding {
+            self.actions.set(Action::Grab, false);
+            self.pickups += 1;
+            if self.pickups % 2 == 0 && storage.items.contains(&Type::Empty) {
+                self.taps.push_back(Action::Store);
+            } else {
+                // at the nearest object, or straight ahead if there is none
+                let forward = self.actions.cursor.unwrap_or(player + Vec2::X);
+                self.actions.cursor = Some(nearest.unwrap_or(forward));
+                self.actions.set(Action::Throw, true);
+                self.wait = THROW_CHARGE_STEPS;
+            }
+            return;
+        }
+        if let Some(slots) = recipe_slots(storage, table) {
+            // selecting the first slot again would add it to the blueprint, hence the clear
+            self.taps.extend([Action::Slot(slots[0]), Action::Clear]);
+            for slot in slots.iter().skip(1) {
+                self.taps.extend([Action::AddToBlueprint, Action::Slot(*slot)]);
+            }
+            self.taps.extend([Action::AddToBlueprint, Action::Synthesize]);
+            return;
+        }
+        let synthesized = storage
+            .items
+            .iter()
+            .position(|item| *item != Type::Empty && !BASIC.contains(item));
+        if let Some(slot) = synthesized {
+            self.taps.extend([Action::Slot(slot), Action::Hold]);
+            return;
+        }
+        if let Some(target) = nearest {
+            self.actions.movement = (target - player).normalize_or_zero();
+            self.actions.cursor = Some(target);
+            self.actions.set(Action::Grab, true);
+        }
+    }
+}
+
+fn random_direction(rng: &mut ChaCha8Rng) -> Vec2 {
+    Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+}
+
+/// Storage slots holding the ingredients of a recipe. Recipes are tried by product, so the
+/// choice doesn't depend on hash order.
+fn recipe_slots(storage: &Storage, table: &Table) -> Option<Vec<usize>> {
+    table
+        .0
+        .iter()
+        .filter_map(|(ingredients, product)| {
+            let mut slots = vec![];
+            for (item, count) in ingredients {
+                for _ in 0..*count {
+                    let slot = (0..storage.items.len())
+                        .find(|i| storage.items[*i] == *item && !slots.contains(i))?;
+                    slots.push(slot);
+                }
+            }
+            Some((*product, slots))
+        })
+        .min_by_key(|(product, _)| *product)
+        .map(|(_, slots)| slots)
+}
+
+fn reset_bot(mut bot: ResMut<Bot>) {
+    *bot = Bot::new(bot.kind);
+}
+
+/// Runs after `begin_step`, replacing the input of the step.
+#[allow(clippy::too_many_arguments)]
+fn bot_system(
+    rng: Res<GameRng>,
+    table: Res<Table>,
+    entity_in_hand: Res<EntityInHand>,
+    mut bot: ResMut<Bot>,
+    mut actions: ResMut<StepActions>,
+    mut metrics: ResMut<Metrics>,
+    player_query: Query<(&RigidBodyPositionComponent, &Storage), With<Player>>,
+    object_query: Query<&RigidBodyPositionComponent, (With<Throwable>, Without<Grabbed>)>,
+) {
+    metrics.steps += 1;
+    let (player_pos, storage) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let player = Vec2::from(player_pos.position.translation.vector);
+    let holding = entity_in_hand.entity.is_some();
+    if bot.rng.is_none() {
+        bot.rng = Some(stream(rng.run_seed, BOT_STREAM));
+    }
+    if !bot.tap() {
+        match bot.kind {
+            BotKind::Random => bot.random_step(player, holding),
+            BotKind::Scripted => {
+                let nearest = object_query
+                    .iter()
+                    .map(|pos| Vec2::from(pos.position.translation.vector))
+                    .min_by(|a, b| {
+                        let (a, b) = (a.distance_squared(player), b.distance_squared(player));
+                        a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                bot.scripted_step(player, holding, storage, &table, nearest);
+            }
+        }
+    }
+    actions.0 = bot.actions.take_step();
+}
+
+fn join<K: std::fmt::Debug, V: Display>(counts: &BTreeMap<K, V>) -> String {
+    if counts.is_empty() {
+        return "none".to_owned();
+    }
+    counts
+        .iter()
+        .map(|(key, value)| format!("{:?} {}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_report(world: &World, minutes: f32, bot: BotKind) {
+    let metrics = metrics(world);
+    let current = (
+        *world.get_resource::<Score>().expect("the score plugin adds the score"),
+        world
+            .get_resource::<RunStats>()
+            .expect("the stats plugin adds the run stats")
+            .clone(),
+    );
+    let all: Vec<&(Score, RunStats)> = metrics.runs.iter().chain([&current]).collect();
+
+    println!(
+        "simulated {} minutes ({} steps) with the {:?} bot",
+        minutes, metrics.steps, bot
+    );
+    let deaths = metrics.runs.len();
+    if deaths == 0 {
+        println!("survival: no deaths, the run lasted {:.1} s", current.0.time);
+    } else {
+        let times: Vec<f32> = metrics.runs.iter().map(|(score, _)| score.time).collect();
+        let average = times.iter().sum::<f32>() / deaths as f32;
+        let shortest = times.iter().copied().fold(f32::INFINITY, f32::min);
+        let longest = times.iter().copied().fold(0.0, f32::max);
+        println!(
+            "survival: {} deaths, average {:.1} s, shortest {:.1} s, longest {:.1} s",
+            deaths, average, shortest, longest
+        );
+    }
+
+    let mut syntheses = BTreeMap::<Type, u32>::new();
+    let mut damage_dealt = BTreeMap::new();
+    let mut damage_taken = BTreeMap::new();
+    let mut causes_of_death = BTreeMap::<String, u32>::new();
+    let (mut destroyed, mut thrown, mut stored) = (0, 0, 0);
+    for (score, stats) in all.iter() {
+        for (product, count) in stats.syntheses.iter() {
+            *syntheses.entry(*product).or_default() += count;
+        }
+        for (kind, amount) in stats.damage_dealt.iter() {
+            *damage_dealt.entry(*kind).or_default() += amount;
+        }
+        for (kind, amount) in stats.damage_taken.iter() {
+            *damage_taken.entry(*kind).or_default() += amount;
+        }
+        destroyed += score.destroyed;
+        thrown += stats.thrown;
+        stored += stats.stored;
+    }
+    for (_, stats) in metrics.runs.iter() {
+        if let Some(hit) = stats.cause_of_death {
+            let cause = match hit.source {
+                Some(source) => format!("{:?} by {:?}", hit.kind, source),
+                None => format!("{:?}", hit.kind),
+            };
+            *causes_of_death.entry(cause).or_default() += 1;
+        }
+    }
+    let total_syntheses: u32 = syntheses.values().sum();
+    println!(
+        "syntheses: {} ({:.2} per minute): {}",
+        total_syntheses,
+        total_syntheses as f32 / minutes,
+        join(&syntheses)
+    );
+    println!(
+        "objects: {} destroyed, {} thrown, {} stored",
+        destroyed, thrown, stored
+    );
+    println!("damage dealt: {}", join(&damage_dealt));
+    println!("damage taken: {}", join(&damage_taken));
+    let by_source: Vec<String> = metrics
+        .damage_taken_by_source
+        .iter()
+        .map(|(source, amount)| format!("{} {}", source, amount))
+        .collect();
+    println!("damage taken by source: {}", by_source.join(", "));
+    let causes: Vec<String> = causes_of_death
+        .iter()
+        .map(|(cause, count)| format!("{} {}", cause, count))
+        .collect();
+    println!("causes of death: {}", causes.join(", "));
+}
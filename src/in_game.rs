@@ -1,71 +1,86 @@
-use bevy::core::FixedTimestep;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_prototype_lyon::prelude::*;
 use bevy_rapier2d::physics::RigidBodyComponentsQueryPayload;
 use bevy_rapier2d::prelude::*;
 
-use super::{AppState, TIME_STEP};
+use super::AppState;
+use crate::action::{clear_action_edges, Action, Device, StepActions};
 use crate::bundle::*;
 use crate::component::*;
+use crate::damage::*;
+use crate::enemy::{AiController, EnemyPlugin};
+use crate::fixed_step::{AddStepSystems, SimTime, StepContacts};
 use crate::magic::*;
+use crate::object_def::ObjectRegistry;
 use crate::particle::*;
+use crate::rng::GameRng;
 use crate::shape_mod::*;
+use crate::stats::RunStats;
 use crate::synthesis::SynthesisPlugin;
 use crate::SpriteAtlasHandle;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::f32::consts::PI;
 
 pub struct InGamePlugin;
 
 impl Plugin for InGamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(MagicPlugin)
+        app.add_plugin(DamagePlugin)
+            .add_plugin(MagicPlugin)
             .add_plugin(SynthesisPlugin)
+            .add_plugin(EnemyPlugin)
             .init_resource::<ObjectToPlayer>()
             .insert_resource(TrailTimer(Timer::from_seconds(0.01, true)))
-            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(spawn_player))
             .add_system_set(
-                SystemSet::on_update(AppState::InGame)
-                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64)),
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(teardown_run.label("teardown_run"))
+                    .with_system(spawn_player.after("teardown_run"))
+                    .with_system(clear_action_edges),
             )
+            // leaving a run for the main menu clears the arena behind it
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(teardown_run))
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
+                    .with_system(charge_indicator_system)
+                    .with_system(player_shadow_system), // .with_system(trail_system)
+            )
+            .add_step_system_set(
+                SystemSet::new()
                     .label("general")
                     .with_system(spawn_objects)
                     .with_system(player_rotate_system)
                     .with_system(player_throw_system)
-                    .with_system(player_movement_system)
-                    .with_system(player_shadow_system), // .with_system(trail_system)
+                    .with_system(player_movement_system),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .with_system(collision_detection)
                     .label("collision_detection"),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .with_system(despawn_dead_entities)
                     .label("despawn_dead_entities"),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .with_system(update_game_state)
                     .after("despawn_dead_entities"),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .label("detection")
                     .with_system(detect_objects_forward),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .after("detection")
                     .before("display")
                     .with_system(player_grab_system),
             )
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_step_system_set(
+                SystemSet::new()
                     .label("display")
                     .with_system(update_shape_of_detected_objects),
             )
@@ -90,18 +105,29 @@ pub struct ObjectToPlayer(pub HashMap<Entity, Entity>);
 #[derive(Default)]
 pub struct SpawnTimer(pub Timer);
 
+/// Impulse of a full power throw of an object of `THROW_REFERENCE_MASS`.
+const THROW_IMPULSE: f32 = 1000.0;
+const THROW_REFERENCE_MASS: f32 = 8.0;
+/// Objects up to this many times `THROW_REFERENCE_MASS` leave the hand at the same speed,
+/// heavier ones slow down. Lighter ones than the low end fly faster.
+const THROW_MASS_RANGE: (f32, f32) = (0.5, 8.0);
+/// Force pushing the player along the movement direction.
+const PLAYER_FORCE: f32 = 3000.0;
+
 fn spawn_objects(
     mut commands: Commands,
+    registry: Res<ObjectRegistry>,
     sprite_atlas_handle: Res<SpriteAtlasHandle>,
-    time: Res<Time>,
+    time: Res<SimTime>,
     mut timer: ResMut<SpawnTimer>,
+    mut rng: ResMut<GameRng>,
     q: Query<&Object>,
 ) {
     if timer.0.tick(time.delta()).just_finished() {
         if q.iter().len() < 10 {
-            let mut rng = thread_rng();
-            let idx = rng.gen_range::<u8, _>(0..BASIC.len() as u8);
+            let idx = rng.gameplay.gen_range::<u8, _>(0..BASIC.len() as u8);
             commands.spawn_object(
+                registry.as_ref(),
                 sprite_atlas_handle.as_ref(),
                 BASIC[idx as usize],
                 [-50.0, 50.0],
@@ -112,25 +138,40 @@ fn spawn_objects(
 
 fn spawn_player(
     mut commands: Commands,
+    registry: Res<ObjectRegistry>,
     sprite_atlas_handle: Res<SpriteAtlasHandle>,
     mut entity_in_hand: ResMut<EntityInHand>,
 ) {
     let sprite_atlas_handle = sprite_atlas_handle.as_ref();
-    let player = commands.spawn_player(sprite_atlas_handle, 0.0, -10.0).id();
+    let player = commands
+        .spawn_player(sprite_atlas_handle, 0.0, -10.0)
+        .insert(PlayerCredit)
+        .id();
     let object = commands
-        .spawn_object(sprite_atlas_handle, Type::Square, [10.0, -10.0])
-        .insert(Grabbed(player))
+        .spawn_object(
+            registry.as_ref(),
+            sprite_atlas_handle,
+            Type::Square,
+            [10.0, -10.0],
+        )
         .id();
-    let axis = Vector::x_axis();
-    let joint = PrismaticJoint::new(axis)
-        .local_anchor1(point![0.0, 0.0])
-        .local_anchor2(point![0.0, 0.0])
-        .limit_axis([7.0, 8.0]);
-    commands
-        .spawn()
-        .insert(JointBuilderComponent::new(joint, player, object));
+    grab_object(&mut commands, player, object, [7.0, 8.0], true);
     entity_in_hand.entity = Some(object);
     println!("spawned {:?} {:?}", player, object);
+
+    let bar = shapes::Rectangle {
+        extents: Vec2::new(40.0, 4.0),
+        origin: RectangleOrigin::BottomLeft,
+    };
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &bar,
+            DrawMode::Fill(FillMode::color(Color::ORANGE)),
+            Transform::default(),
+        ))
+        .insert(Visibility { is_visible: false })
+        .insert(ChargeIndicator)
+        .insert(RunScoped);
 }
 
 fn player_shadow_system(
@@ -149,8 +190,81 @@ fn player_shadow_system(
     }
 }
 
+/// Hold `object` in front of `holder`, between `limits` along the holder's facing.
+/// Shared by the player and enemies: what the object destroys counts for the player
+/// from now on if `by_player`, and no longer does otherwise, see `PlayerCredit`.
+pub fn grab_object(
+    commands: &mut Commands,
+    holder: Entity,
+    object: Entity,
+    limits: [f32; 2],
+    by_player: bool,
+) {
+    let axis = Vector::x_axis();
+    let joint = PrismaticJoint::new(axis)
+        .local_anchor1(point![0.0, 0.0])
+        .local_anchor2(point![0.0, 0.0])
+        .limit_axis(limits);
+    commands
+        .spawn()
+        .insert(JointBuilderComponent::new(joint, holder, object))
+        .insert(RunScoped);
+    let mut object_commands = commands.entity(object);
+    object_commands.insert(Grabbed(holder));
+    if by_player {
+        object_commands.insert(PlayerCredit);
+    } else {
+        object_commands.remove::<PlayerCredit>();
+    }
+}
+
+/// Launch a held object along `rot` at `power`, scaled by its mass. The holder lets go of it
+/// separately.
+pub fn throw_impulse(
+    vel: &mut RigidBodyVelocityComponent,
+    mprops: &RigidBodyMassPropsComponent,
+    rot: nalgebra::UnitComplex<f32>,
+    power: f32,
+) {
+    let mass_scale =
+        (mprops.mass() / THROW_REFERENCE_MASS).clamp(THROW_MASS_RANGE.0, THROW_MASS_RANGE.1);
+    let dir_scale = power * THROW_IMPULSE * mass_scale;
+    vel.apply_impulse(
+        mprops,
+        Vec2::new(rot.cos_angle() * dir_scale, rot.sin_angle() * dir_scale).into(),
+    )
+}
+
+/// Turn a body towards `dir`. Returns the angle left to turn, in radians.
+pub fn turn_towards(
+    pos: &RigidBodyPositionComponent,
+    vel: &mut RigidBodyVelocityComponent,
+    dir: Vec2,
+) -> f32 {
+    use nalgebra::UnitComplex;
+    let target_rot = UnitComplex::new(dir.y.atan2(dir.x));
+    let rot = pos.position.rotation.angle_to(&target_rot);
+    vel.angvel = rot / PI * 20.0;
+    rot
+}
+
+/// Push a body along `movement` with `force`, or brake it when `movement` is zero.
+pub fn drive(
+    vel: &mut RigidBodyVelocityComponent,
+    forces: &mut RigidBodyForcesComponent,
+    movement: Vec2,
+    force: f32,
+) {
+    if movement != Vec2::ZERO {
+        forces.force = (movement * force).into();
+    } else {
+        vel.linvel *= 0.9;
+    }
+}
+
+/// Face the cursor, or the right stick when playing with a gamepad.
 fn player_rotate_system(
-    windows: Res<Windows>,
+    actions: Res<StepActions>,
     mut player: Query<
         (
             &RigidBodyPositionComponent,
@@ -160,60 +274,56 @@ fn player_rotate_system(
         (With<Player>, Without<Paralyzed>),
     >,
 ) {
-    let window = windows.get_primary().unwrap();
-    if let Some(pos) = window.cursor_position() {
-        for (player_pos, mut player_vel, _player_mprops) in player.iter_mut() {
-            use nalgebra::UnitComplex;
-            let size = Vec2::new(window.width() as f32, window.height() as f32);
-            let pos = pos - size / 2.0;
-            let cursor_rot = UnitComplex::new(pos.y.atan2(pos.x));
-            let rot = player_pos.position.rotation.angle_to(&cursor_rot);
-            player_vel.angvel = rot / PI * 20.0;
+    for (player_pos, mut player_vel, _player_mprops) in player.iter_mut() {
+        let dir = match actions.device {
+            Device::KeyboardMouse => actions
+                .cursor
+                .map(|cursor| cursor - Vec2::from(player_pos.position.translation.vector)),
+            Device::Gamepad(_) => actions.aim,
+        };
+        match dir {
+            Some(dir) => {
+                turn_towards(player_pos, &mut player_vel, dir);
+            }
+            None if actions.device != Device::KeyboardMouse => player_vel.angvel = 0.0,
+            None => {}
         }
     }
 }
 
 fn player_grab_system(
     mut commands: Commands,
-    buttons: Res<Input<MouseButton>>,
+    actions: Res<StepActions>,
     // mut object_to_player: ResMut<ObjectToPlayer>,
     entity_in_range: Res<EntityInRange>,
     mut entity_in_hand: ResMut<EntityInHand>,
     player_query: Query<Entity, With<Player>>,
 ) {
     // println!("{:?}", entity_in_hand.entity);
-    if entity_in_hand.entity.is_none() && buttons.pressed(MouseButton::Left) {
+    if entity_in_hand.entity.is_none() && actions.pressed(Action::Grab) {
         if let Some(object_entity) = entity_in_range.cur {
             let player_entity = player_query.single();
-            let axis = Vector::x_axis();
-            let joint = PrismaticJoint::new(axis)
-                .local_anchor1(point![0.0, 0.0])
-                .local_anchor2(point![0.0, 0.0])
-                .limit_axis([4.0, 7.0]);
-            commands.spawn().insert(JointBuilderComponent::new(
-                joint,
-                player_entity,
-                object_entity,
-            ));
+            grab_object(&mut commands, player_entity, object_entity, [4.0, 7.0], true);
             entity_in_hand.entity = Some(object_entity);
-            commands
-                .entity(object_entity)
-                .insert(Grabbed(player_entity));
             // object_to_player.0.insert(object_entity, player_entity);
             println!("new joint built with {:?}", object_entity);
         }
     }
 }
 
+/// Hold Space to charge, release to throw.
+#[allow(clippy::too_many_arguments)]
 fn player_throw_system(
     mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<SimTime>,
+    actions: Res<StepActions>,
+    mut stats: ResMut<RunStats>,
     // mut object_to_player: ResMut<ObjectToPlayer>,
     mut joint_set: ResMut<ImpulseJointSet>,
     mut island_manager: ResMut<IslandManager>,
     mut entity_in_hand: ResMut<EntityInHand>,
     mut q: QuerySet<(
-        QueryState<(Entity, &RigidBodyPositionComponent), With<Player>>,
+        QueryState<(Entity, &RigidBodyPositionComponent, &mut ThrowCharge), With<Player>>,
         QueryState<
             (
                 &mut RigidBodyVelocityComponent,
@@ -224,37 +334,68 @@ fn player_throw_system(
         QueryState<RigidBodyComponentsQueryPayload>,
     )>,
 ) {
-    if entity_in_hand.entity.is_some() && keyboard_input.pressed(KeyCode::Space) {
-        use nalgebra::UnitComplex;
-        let (player, player_pos): (Entity, _) = q.q0().single();
-        let rigid_body_handle: RigidBodyHandle = player.handle();
-        let rot: UnitComplex<f32> = player_pos.position.rotation;
-        let dir_x = rot.cos_angle();
-        let dir_y = rot.sin_angle();
-        let dir_scale = 1000.0;
-
-        let iter = joint_set.joints_with(rigid_body_handle);
-        let mut object_query = q.q1();
-        for (_h1, h2, _j) in iter {
-            let (mut obj_vel, obj_mprops) = object_query.get_mut(h2.entity()).unwrap();
-            // object.force = Vec2::new(dir_x * dir_scale, dir_y * dir_scale).into();
-            obj_vel.apply_impulse(
-                obj_mprops,
-                Vec2::new(dir_x * dir_scale, dir_y * dir_scale).into(),
-            )
+    use nalgebra::UnitComplex;
+    let (player, rot, power): (Entity, UnitComplex<f32>, f32) = {
+        let mut player_query = q.q0();
+        let (player, player_pos, mut charge) = player_query.single_mut();
+        if entity_in_hand.entity.is_none() {
+            charge.held = None;
+            return;
         }
+        if actions.just_pressed(Action::Throw) {
+            charge.held = Some(0.0);
+        }
+        if let Some(held) = charge.held.as_mut() {
+            *held += time.delta_seconds();
+        }
+        if !actions.just_released(Action::Throw) {
+            return;
+        }
+        match charge.power() {
+            Some(power) => {
+                charge.held = None;
+                (player, player_pos.position.rotation, power)
+            }
+            None => return,
+        }
+    };
+
+    let rigid_body_handle: RigidBodyHandle = player.handle();
+    let iter = joint_set.joints_with(rigid_body_handle);
+    let mut object_query = q.q1();
+    for (_h1, h2, _j) in iter {
+        let (mut obj_vel, obj_mprops) = object_query.get_mut(h2.entity()).unwrap();
+        throw_impulse(&mut obj_vel, obj_mprops, rot, power);
+    }
+
+    let mut rigid_body_set = RigidBodyComponentsSet(q.q2());
+    joint_set.remove_joints_attached_to_rigid_body(
+        rigid_body_handle,
+        &mut island_manager,
+        &mut rigid_body_set,
+    );
+    commands
+        .entity(entity_in_hand.entity.unwrap())
+        .remove::<Grabbed>();
+    stats.thrown += 1;
+    // object_to_player.0.remove(&entity_in_hand.entity.unwrap());
+    entity_in_hand.entity = None;
+}
 
-        let mut rigid_body_set = RigidBodyComponentsSet(q.q2());
-        joint_set.remove_joints_attached_to_rigid_body(
-            rigid_body_handle,
-            &mut island_manager,
-            &mut rigid_body_set,
-        );
-        commands
-            .entity(entity_in_hand.entity.unwrap())
-            .remove::<Grabbed>();
-        // object_to_player.0.remove(&entity_in_hand.entity.unwrap());
-        entity_in_hand.entity = None;
+/// Follow the player and show the power of the throw being charged.
+fn charge_indicator_system(
+    player_query: Query<(&Transform, &ThrowCharge), With<Player>>,
+    mut indicator_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<ChargeIndicator>, Without<Player>),
+    >,
+) {
+    if let (Ok((player_pos, charge)), Ok((mut pos, mut visibility))) =
+        (player_query.get_single(), indicator_query.get_single_mut())
+    {
+        visibility.is_visible = charge.held.is_some();
+        pos.translation = player_pos.translation + Vec3::new(-20.0, -30.0, 30.0);
+        pos.scale.x = charge.power().unwrap_or(0.0);
     }
 }
 
@@ -262,7 +403,8 @@ fn detect_objects_forward(
     query_pipeline: Res<QueryPipeline>,
     collider_query: QueryPipelineColliderComponentsQuery,
     player_query: Query<&RigidBodyPositionComponent, With<Player>>,
-    throwable_query: Query<&Throwable>,
+    // an object held by an enemy can't be taken from it
+    throwable_query: Query<&Throwable, Without<Grabbed>>,
     mut entity_in_range: ResMut<EntityInRange>,
 ) {
     let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
@@ -297,21 +439,18 @@ fn detect_objects_forward(
 
 fn update_shape_of_detected_objects(
     mut entity_in_range: ResMut<EntityInRange>,
-    mut query: Query<(&mut DrawMode, &Throwable)>,
+    mut query: Query<&mut DrawMode, With<Throwable>>,
 ) {
     if entity_in_range.prev != entity_in_range.cur {
         if let Some(entity) = entity_in_range.prev {
             match query.get_mut(entity) {
-                Ok((mode, id)) => match mode.into_inner() {
+                Ok(mode) => match mode.into_inner() {
                     DrawMode::Outlined {
                         fill_mode: _,
                         outline_mode,
                     } => {
                         let c = outline_mode.color.as_hlsa_f32();
-                        *outline_mode = StrokeMode::new(
-                            Color::hsl(c[0], c[1], 0.4).into(),
-                            5.0 * SCALE[id.0 as usize],
-                        );
+                        outline_mode.color = Color::hsl(c[0], c[1], 0.4);
                     }
                     _ => {}
                 },
@@ -320,16 +459,13 @@ fn update_shape_of_detected_objects(
         }
         if let Some(entity) = entity_in_range.cur {
             match query.get_mut(entity) {
-                Ok((mode, id)) => match mode.into_inner() {
+                Ok(mode) => match mode.into_inner() {
                     DrawMode::Outlined {
                         fill_mode: _,
                         outline_mode,
                     } => {
                         let c = outline_mode.color.as_hlsa_f32();
-                        *outline_mode = StrokeMode::new(
-                            Color::hsl(c[0], c[1], 0.1).into(),
-                            5.0 * SCALE[id.0 as usize],
-                        );
+                        outline_mode.color = Color::hsl(c[0], c[1], 0.1);
                     }
                     _ => {}
                 },
@@ -343,7 +479,7 @@ fn update_shape_of_detected_objects(
 
 fn player_movement_system(
     app_state: Res<State<AppState>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<StepActions>,
     mut player: Query<
         (
             &mut RigidBodyVelocityComponent,
@@ -355,62 +491,62 @@ fn player_movement_system(
     if *app_state.current() == AppState::EndGame {
         return;
     }
-    let (mut dir_x, mut dir_y) = (0.0, 0.0);
-    let mut pressed = false;
-    if keyboard_input.any_pressed([KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D]) {
-        if keyboard_input.pressed(KeyCode::A) {
-            dir_x -= 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::D) {
-            dir_x += 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::W) {
-            dir_y += 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::S) {
-            dir_y -= 1.0;
-        }
-        pressed = true;
-    }
     for (mut player_vel, mut player_forces) in player.iter_mut() {
-        let dir_scale = 3000.0;
-
-        if pressed {
-            player_forces.force = Vec2::new(dir_x * dir_scale, dir_y * dir_scale).into();
-        } else {
-            player_vel.linvel *= 0.9;
-        }
+        drive(&mut player_vel, &mut player_forces, actions.movement, PLAYER_FORCE);
     }
 }
 
-/// # Safety: h1 and h2 should be different
+/// Both sides of a contact take damage from the same impulse, each against its own threshold.
+/// Contact events can name colliders whose entity is already despawned, so both lookups may fail.
 fn collision_detection(
-    mut contact_events: EventReader<ContactEvent>,
-    q: Query<(&mut Health, &mut Dmg, &RigidBodyVelocityComponent)>,
+    contacts: Res<StepContacts>,
+    mut ev_damage: EventWriter<DamageEvent>,
+    q: Query<
+        (
+            &Dmg,
+            &RigidBodyVelocityComponent,
+            &RigidBodyMassPropsComponent,
+            &RigidBodyTypeComponent,
+            Option<&ImpactThreshold>,
+        ),
+        With<Health>,
+    >,
 ) {
-    for contact_event in contact_events.iter() {
-        match contact_event {
-            ContactEvent::Started(h1, h2) => {
-                unsafe {
-                    // Todo: check why h1 exists but h1.entity() doesn't exist
-                    let query1 = q.get_unchecked(h1.entity());
-                    let query2 = q.get_unchecked(h2.entity());
-                    if query1.is_ok() && query2.is_ok() {
-                        let (mut health1, dmg1, vel1): (
-                            Mut<Health>,
-                            Mut<Dmg>,
-                            &RigidBodyVelocityComponent,
-                        ) = query1.unwrap();
-                        let (mut health2, dmg2, vel2) = query2.unwrap();
-                        let rel_linvel = vel1.linvel - vel2.linvel;
-                        if rel_linvel.norm() > 80.0 {
-                            health1.hp -= dmg2.0;
-                            health2.hp -= dmg1.0;
-                        }
+    let inv_mass = |mprops: &RigidBodyMassPropsComponent, body_type: &RigidBodyTypeComponent| {
+        if body_type.is_dynamic() {
+            1.0 / mprops.mass()
+        } else {
+            0.0
+        }
+    };
+    for contact_event in contacts.0.iter() {
+        if let ContactEvent::Started(h1, h2) = contact_event {
+            let (e1, e2) = (h1.entity(), h2.entity());
+            if let (Ok(q1), Ok(q2)) = (q.get(e1), q.get(e2)) {
+                let (dmg1, vel1, mprops1, type1, threshold1) = q1;
+                let (dmg2, vel2, mprops2, type2, threshold2) = q2;
+                let rel_speed = (vel1.linvel - vel2.linvel).norm();
+                let impulse = impact_impulse(
+                    inv_mass(mprops1, type1),
+                    inv_mass(mprops2, type2),
+                    rel_speed,
+                );
+                for (target, source, dmg, threshold) in [
+                    (e1, e2, dmg2.0, threshold1),
+                    (e2, e1, dmg1.0, threshold2),
+                ] {
+                    let threshold = threshold.map_or(0.0, |threshold| threshold.0);
+                    let amount = impact_damage(dmg, impulse, threshold);
+                    if amount > 0 {
+                        ev_damage.send(DamageEvent {
+                            target,
+                            source: Some(source),
+                            amount,
+                            kind: DamageKind::Impact,
+                        });
                     }
                 }
             }
-            _ => {}
         }
     }
 }
@@ -447,14 +583,15 @@ fn despawn_dead_entities(
             &Transform,
             Option<&Grabbed>,
             Option<&Player>,
+            Option<&AiController>,
         ),
-        Without<Undead>,
+        (Without<Undead>, Without<Explode>),
     >,
     q1: Query<RigidBodyComponentsQueryPayload>,
 ) {
     // println!("indespawn");
     let mut rigid_body_set = RigidBodyComponentsSet(q1);
-    for (e, health, pos, grabbed, player) in q0.iter() {
+    for (e, health, pos, grabbed, player, ai) in q0.iter() {
         if health.hp <= 0 {
             particle_ev.send(ScatteringParticles {
                 pos: Vec3::new(pos.translation.x, pos.translation.y, 15.0),
@@ -463,15 +600,21 @@ fn despawn_dead_entities(
                 vel_scale: 3.0,
                 ..Default::default()
             });
-            if player.is_some() || grabbed.is_some() {
+            if player.is_some() || grabbed.is_some() || ai.is_some() {
                 let rigid_body_handle: RigidBodyHandle = e.handle();
                 joint_set.remove_joints_attached_to_rigid_body(
                     rigid_body_handle,
                     &mut island_manager,
                     &mut rigid_body_set,
                 );
+            }
+            if player.is_some() || entity_in_hand.entity == Some(e) {
                 entity_in_hand.entity = None;
             }
+            // a dead enemy drops what it holds
+            if let Some(held) = ai.and_then(|ai| ai.held) {
+                commands.entity(held).remove::<Grabbed>();
+            }
             if player.is_some() {
                 println!("player dead: {:?}", health.hp);
             }
@@ -481,6 +624,38 @@ fn despawn_dead_entities(
     }
 }
 
+/// Despawn everything the previous run spawned and reset the run's resources,
+/// so every run, however it was restarted, starts from a clean arena.
+/// Resources are reset in place: `spawn_player` uses them right after.
+#[allow(clippy::too_many_arguments)]
+fn teardown_run(
+    mut commands: Commands,
+    mut joint_set: ResMut<ImpulseJointSet>,
+    mut island_manager: ResMut<IslandManager>,
+    mut entity_in_range: ResMut<EntityInRange>,
+    mut entity_in_hand: ResMut<EntityInHand>,
+    mut storage_in_hand: ResMut<StorageInHand>,
+    mut spawn_timer: ResMut<SpawnTimer>,
+    run_query: Query<(Entity, Option<&Player>, Option<&Enemy>), With<RunScoped>>,
+    rigid_bodies: Query<RigidBodyComponentsQueryPayload>,
+) {
+    let mut rigid_body_set = RigidBodyComponentsSet(rigid_bodies);
+    for (e, player, enemy) in run_query.iter() {
+        if player.is_some() || enemy.is_some() {
+            joint_set.remove_joints_attached_to_rigid_body(
+                e.handle(),
+                &mut island_manager,
+                &mut rigid_body_set,
+            );
+        }
+        commands.entity(e).despawn_recursive();
+    }
+    *entity_in_range = EntityInRange::default();
+    *entity_in_hand = EntityInHand::default();
+    *storage_in_hand = StorageInHand::default();
+    spawn_timer.0.reset();
+}
+
 fn update_game_state(
     mut app_state: ResMut<State<AppState>>,
     player_health: Query<&Health, With<Player>>,
@@ -495,3 +670,36 @@ fn update_game_state(
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn throw_launches_the_held_object() {
+        let mut app = TestApp::new();
+        let held = app.resource::<EntityInHand>().entity.unwrap();
+        app.press(KeyCode::Space);
+        app.steps(30);
+        app.release(KeyCode::Space);
+        app.step();
+        assert!(app.resource::<EntityInHand>().entity.is_none());
+        assert!(app.get::<Grabbed>(held).is_none());
+        assert_eq!(app.resource::<RunStats>().thrown, 1);
+        let vel = app.get::<RigidBodyVelocityComponent>(held).unwrap();
+        // the player faces +x, where the object is held
+        assert!(vel.linvel.x > 0.0);
+    }
+
+    #[test]
+    fn death_ends_the_run() {
+        let mut app = TestApp::new();
+        let player = app.player();
+        app.damage(player, 100);
+        // the step kills the player, the state changes on the next update
+        app.steps(2);
+        assert_eq!(app.state(), AppState::EndGame);
+        assert!(app.app.world.get_entity(player).is_none());
+    }
+}
@@ -3,38 +3,45 @@ use bevy_rapier2d::prelude::*;
 
 use crate::camera::*;
 use crate::component::*;
+use crate::damage::*;
+use crate::fixed_step::{AddStepSystems, SimTime};
 use crate::particle::*;
-use crate::AppState;
-use bevy::utils::Duration;
+use bevy::ecs::system::EntityCommands;
+use bevy::utils::{Duration, HashMap, HashSet};
+use serde::Deserialize;
+use std::marker::PhantomData;
 
 pub struct MagicPlugin;
 
 impl Plugin for MagicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(AppState::InGame)
+        app.init_resource::<EffectReactions>().add_step_system_set(
+            SystemSet::new()
                 .with_system(heal_timer_system)
                 .with_system(heal_system)
                 .with_system(heal_animation_system)
                 .with_system(sight_system)
-                .with_system(magic_timer_system::<Frozen>)
-                .with_system(freeze_src_system)
                 .with_system(frozen_system)
                 .with_system(frozen_animation_system)
-                .with_system(magic_timer_system::<Burned>)
-                .with_system(burn_src_system)
                 .with_system(burned_system)
-                .with_system(magic_timer_system::<Paralyzed>)
-                .with_system(paralyze_src_system)
                 .with_system(paralyzed_system)
                 .with_system(paralyzed_animation_system)
-                .before("despawn_dead_entities"),
+                .with_system(effect_reaction_system)
+                .before("apply_damage"),
         )
-        .add_system_set(
-            SystemSet::on_update(AppState::InGame)
+        .add_contact_effect::<Frozen>()
+        .add_contact_effect::<Burned>()
+        .add_contact_effect::<Paralyzed>()
+        .add_step_system_set(
+            SystemSet::new()
                 .with_system(explode_system)
                 .after("collision_detection")
-                .before("despawn_dead_entities"),
+                .before("apply_damage"),
+        )
+        .add_step_system_set(
+            SystemSet::new()
+                .with_system(explode_credit_system)
+                .after("apply_damage"),
         );
     }
 }
@@ -45,16 +52,187 @@ trait MagicWithTimer {
 
 fn magic_timer_system<T: MagicWithTimer + Component>(
     mut commands: Commands,
-    time: Res<Time>,
-    mut magic_query: Query<(Entity, &mut T)>,
+    time: Res<SimTime>,
+    mut magic_query: Query<(Entity, &mut T, Option<&ImmunityAfter<T>>)>,
 ) {
-    for (e, mut magic) in magic_query.iter_mut() {
+    for (e, mut magic, immunity) in magic_query.iter_mut() {
         if magic.tick(time.delta()).just_finished() {
             commands.entity(e).remove::<T>();
+            if let Some(immunity) = immunity {
+                commands
+                    .entity(e)
+                    .remove::<ImmunityAfter<T>>()
+                    .insert(Immune::<T>::new(immunity.0));
+            }
         };
     }
 }
 
+/// Which entities a `ContactEffectSource` applies its effect to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Targets {
+    /// The player and enemies.
+    Characters,
+    Objects,
+    Both,
+}
+
+impl Targets {
+    fn accepts(&self, is_character: bool, is_object: bool) -> bool {
+        match self {
+            Targets::Characters => is_character,
+            Targets::Objects => is_object,
+            Targets::Both => is_character || is_object,
+        }
+    }
+}
+
+/// What a new contact does to a target that already has the effect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stacking {
+    /// Restart the duration.
+    Refresh,
+    /// Add one stack of intensity, up to `max` stacks, and restart the duration.
+    Stack { max: u32 },
+    /// Leave the active effect untouched.
+    Ignore,
+}
+
+/// A status effect applied by `ContactEffectSource`.
+trait ContactEffect: MagicWithTimer + Component + Clone {
+    fn refresh(&mut self);
+    fn add_stack(&mut self, max: u32);
+}
+
+/// Apply a copy of `effect` to every target that starts touching the holder.
+/// If `immune`, holders of the same kind of source never receive the effect.
+/// If `immunity` is set, targets can't receive the effect again for that many seconds
+/// after it ends.
+#[derive(Component)]
+pub struct ContactEffectSource<E: Component + Clone> {
+    pub effect: E,
+    pub targets: Targets,
+    pub immune: bool,
+    pub stacking: Stacking,
+    pub immunity: Option<f32>,
+}
+
+impl<E: Component + Clone> ContactEffectSource<E> {
+    pub fn generate_effect(&self) -> E {
+        self.effect.clone()
+    }
+}
+
+/// Seconds of `Immune<E>` granted once the effect `E` on this entity ends.
+#[derive(Component)]
+struct ImmunityAfter<E: Component>(f32, PhantomData<E>);
+
+/// The holder can't receive `E` until `timer` is finished.
+#[derive(Component)]
+pub struct Immune<E: Component> {
+    timer: Timer,
+    _effect: PhantomData<E>,
+}
+
+impl<E: Component> Immune<E> {
+    fn new(duration: f32) -> Self {
+        Immune {
+            timer: Timer::from_seconds(duration, false),
+            _effect: PhantomData,
+        }
+    }
+}
+
+trait AddContactEffect {
+    /// Add the systems shared by every contact effect: applying it on contact, removing it once
+    /// its timer is finished and the immunity window. The effect itself only needs its own tick
+    /// behaviour.
+    fn add_contact_effect<E: ContactEffect>(&mut self) -> &mut Self;
+}
+
+impl AddContactEffect for App {
+    fn add_contact_effect<E: ContactEffect>(&mut self) -> &mut Self {
+        self.add_step_system_set(
+            SystemSet::new()
+                .with_system(magic_timer_system::<E>)
+                .with_system(immunity_timer_system::<E>)
+                .with_system(contact_effect_system::<E>)
+                .before("despawn_dead_entities"),
+        )
+    }
+}
+
+fn immunity_timer_system<E: Component>(
+    mut commands: Commands,
+    time: Res<SimTime>,
+    mut immune_query: Query<(Entity, &mut Immune<E>)>,
+) {
+    for (e, mut immune) in immune_query.iter_mut() {
+        if immune.timer.tick(time.delta()).just_finished() {
+            commands.entity(e).remove::<Immune<E>>();
+        }
+    }
+}
+
+/// Effects are applied when a contact starts rather than on every step of contact,
+/// so a long contact doesn't keep resetting the timer.
+/// # Bug in Rapier: contact pairs sometimes contain despawned entities.Must check validity before use.
+fn contact_effect_system<E: ContactEffect>(
+    mut commands: Commands,
+    narrow_phase: Res<NarrowPhase>,
+    mut touching: Local<HashSet<(Entity, Entity)>>,
+    src_query: Query<(Entity, &ContactEffectSource<E>)>,
+    target_query: Query<
+        (Option<&Player>, Option<&Enemy>, Option<&Object>),
+        Or<(With<Player>, With<Enemy>, With<Object>)>,
+    >,
+    mut effect_query: Query<&mut E>,
+    immune_query: Query<(), With<Immune<E>>>,
+) {
+    let mut now_touching = HashSet::default();
+    for (e, src) in src_query.iter() {
+        for contact_pair in narrow_phase.contacts_with(e.handle()) {
+            if contact_pair.has_any_active_contact {
+                let other_collider = if contact_pair.collider1 == e.handle() {
+                    contact_pair.collider2
+                } else {
+                    contact_pair.collider1
+                };
+                let other_e = other_collider.entity();
+
+                if let Ok((player, enemy, object)) = target_query.get(other_e) {
+                    let is_character = player.is_some() || enemy.is_some();
+                    if !src.targets.accepts(is_character, object.is_some()) {
+                        continue;
+                    }
+                    if src.immune && src_query.get(other_e).is_ok() {
+                        continue;
+                    }
+                    now_touching.insert((e, other_e));
+                    if touching.contains(&(e, other_e)) || immune_query.get(other_e).is_ok() {
+                        continue;
+                    }
+                    match effect_query.get_mut(other_e) {
+                        Ok(mut active) => match src.stacking {
+                            Stacking::Refresh => active.refresh(),
+                            Stacking::Stack { max } => active.add_stack(max),
+                            Stacking::Ignore => {}
+                        },
+                        Err(_) => {
+                            let mut target = commands.entity(other_e);
+                            target.insert(src.generate_effect());
+                            if let Some(immunity) = src.immunity {
+                                target.insert(ImmunityAfter::<E>(immunity, PhantomData));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    *touching = now_touching;
+}
+
 /// Heal: heal holder everytime `timer` is finished. May use negative hp for self-damage.
 #[derive(Component)]
 pub struct Heal {
@@ -71,7 +249,7 @@ impl Heal {
     }
 }
 
-fn heal_timer_system(time: Res<Time>, mut magic_query: Query<&mut Heal, With<Grabbed>>) {
+fn heal_timer_system(time: Res<SimTime>, mut magic_query: Query<&mut Heal, With<Grabbed>>) {
     for mut heal in magic_query.iter_mut() {
         heal.timer.tick(time.delta());
     }
@@ -87,17 +265,20 @@ fn heal_system(object_query: Query<(&Heal, &Grabbed)>, mut player_query: Query<&
     }
 }
 
+/// Particles around whoever holds the object, the player or an enemy.
 fn heal_animation_system(
     mut ev_particle: EventWriter<ScatteringParticles>,
     object_query: Query<(&Heal, &Grabbed)>,
-    player_query: Query<&Transform, With<Player>>,
+    holder_query: Query<&Transform>,
 ) {
     for (heal, grabbed) in object_query.iter() {
         if heal.timer.just_finished() {
-            let player_entity = grabbed.0;
-            let player_pos = player_query.get(player_entity).unwrap();
+            let holder_pos = match holder_query.get(grabbed.0) {
+                Ok(holder_pos) => holder_pos,
+                Err(_) => continue,
+            };
             ev_particle.send(ScatteringParticles {
-                pos: Vec3::new(player_pos.translation.x, player_pos.translation.y, 20.0),
+                pos: Vec3::new(holder_pos.translation.x, holder_pos.translation.y, 20.0),
                 num: 3,
                 color: Color::rgb_u8(184, 248, 174),
                 ..Default::default()
@@ -118,44 +299,53 @@ impl Sight {
     }
 }
 
+/// Only a `Sight` held by the player widens the view, enemies holding one don't.
 fn sight_system(
     mut camera: Query<(&mut OrthographicProjection, &MainCamera)>,
-    object_query: Query<&Sight, With<Grabbed>>,
+    object_query: Query<(&Sight, &Grabbed)>,
+    player_query: Query<(), With<Player>>,
 ) {
-    let (mut camera_config, camera) = camera.single_mut();
-    if object_query.is_empty() {
-        camera_config.scale = (camera_config.scale - camera.speed_z).max(1.0);
-    } else {
-        let sight = object_query.single();
-        camera_config.scale = (camera_config.scale + camera.speed_z).min(sight.scale);
+    // a headless simulation has no camera
+    let (mut camera_config, camera) = match camera.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let sight = object_query
+        .iter()
+        .find(|(_, grabbed)| player_query.get(grabbed.0).is_ok());
+    match sight {
+        Some((sight, _)) => {
+            camera_config.scale = (camera_config.scale + camera.speed_z).min(sight.scale);
+        }
+        None => camera_config.scale = (camera_config.scale - camera.speed_z).max(1.0),
     }
 }
 
-/// Apply `Frozen` upon hitting players and objects.
-#[derive(Component)]
-pub struct FreezeSource {
-    scale: f32,
-    duration: f32,
-}
+/// Apply `Frozen` upon hitting the player, enemies and objects.
+pub type FreezeSource = ContactEffectSource<Frozen>;
 
 impl FreezeSource {
     pub fn new(scale: f32, duration: f32) -> Self {
-        FreezeSource { scale, duration }
-    }
-
-    pub fn generate_effect(&self) -> Frozen {
-        Frozen {
-            scale: self.scale,
-            duration: Timer::from_seconds(self.duration, false),
+        ContactEffectSource {
+            effect: Frozen {
+                scale,
+                stacks: 1,
+                duration: Timer::from_seconds(duration, false),
+            },
+            targets: Targets::Both,
+            immune: true,
+            stacking: Stacking::Refresh,
+            immunity: None,
         }
     }
 }
 
-/// Slows down speed during some period.
+/// Slows down speed during some period. Each stack multiplies the slowdown.
 /// Frozen entities don't apply `Frozen` upon collision (things become very complex if applied).
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Frozen {
     scale: f32,
+    stacks: u32,
     duration: Timer,
 }
 
@@ -165,40 +355,22 @@ impl MagicWithTimer for Frozen {
     }
 }
 
-/// # Bug in Rapier: contact pairs sometimes contain despawned entities.Must check validity before use.
-fn freeze_src_system(
-    mut commands: Commands,
-    narrow_phase: Res<NarrowPhase>,
-    freeze_src_query: Query<(Entity, &FreezeSource)>,
-    frozenable_query: Query<(), Or<(With<Player>, With<Object>)>>,
-) {
-    for (e, freeze_src) in freeze_src_query.iter() {
-        for contact_pair in narrow_phase.contacts_with(e.handle()) {
-            if contact_pair.has_any_active_contact {
-                let other_collider = if contact_pair.collider1 == e.handle() {
-                    contact_pair.collider2
-                } else {
-                    contact_pair.collider1
-                };
-                let other_e = other_collider.entity();
+impl ContactEffect for Frozen {
+    fn refresh(&mut self) {
+        self.duration.reset();
+    }
 
-                if frozenable_query.get(other_e).is_ok() {
-                    if freeze_src_query.get(other_e).is_err() {
-                        // println!("src: {:?}, frozen: {:?}", e, other_e);
-                        commands
-                            .entity(other_e)
-                            .insert(freeze_src.generate_effect());
-                    }
-                }
-            }
-        }
+    fn add_stack(&mut self, max: u32) {
+        self.stacks = (self.stacks + 1).min(max);
+        self.duration.reset();
     }
 }
 
 fn frozen_system(mut frozen_query: Query<(&mut RigidBodyVelocityComponent, &Frozen)>) {
     for (mut vel, frozen) in frozen_query.iter_mut() {
-        vel.linvel *= frozen.scale;
-        vel.angvel *= frozen.scale;
+        let scale = frozen.scale.powi(frozen.stacks as i32);
+        vel.linvel *= scale;
+        vel.angvel *= scale;
     }
 }
 
@@ -217,37 +389,32 @@ fn frozen_animation_system(
     }
 }
 
-/// Apply `Burned` upon hitting players and objects.
-#[derive(Component)]
-pub struct BurnSource {
-    dmg: i32,
-    duration: f32,
-    interval: f32,
-}
+/// Apply `Burned` upon hitting the player, enemies and objects.
+pub type BurnSource = ContactEffectSource<Burned>;
 
 impl BurnSource {
     pub fn new(dmg: i32, duration: f32, interval: f32) -> Self {
-        BurnSource {
-            dmg,
-            duration,
-            interval,
-        }
-    }
-
-    pub fn generate_effect(&self) -> Burned {
-        Burned {
-            dmg: self.dmg,
-            duration: Timer::from_seconds(self.duration, false),
-            interval: Timer::from_seconds(self.interval, true),
+        ContactEffectSource {
+            effect: Burned {
+                dmg,
+                stacks: 1,
+                duration: Timer::from_seconds(duration, false),
+                interval: Timer::from_seconds(interval, true),
+            },
+            targets: Targets::Both,
+            immune: true,
+            stacking: Stacking::Stack { max: 3 },
+            immunity: None,
         }
     }
 }
 
-/// Lose a set amount of hp during some period.
+/// Lose a set amount of hp per stack during some period.
 /// Burned entities don't apply `Burned` upon collision (things become very complex if applied).
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Burned {
     dmg: i32,
+    stacks: u32,
     duration: Timer,
     interval: Timer,
 }
@@ -259,36 +426,30 @@ impl MagicWithTimer for Burned {
     }
 }
 
-/// # Bug in Rapier: contact pairs sometimes contain despawned entities.Must check validity before use.
-fn burn_src_system(
-    mut commands: Commands,
-    narrow_phase: Res<NarrowPhase>,
-    burn_src_query: Query<(Entity, &BurnSource)>,
-) {
-    for (e, burn_src) in burn_src_query.iter() {
-        for contact_pair in narrow_phase.contacts_with(e.handle()) {
-            if contact_pair.has_any_active_contact {
-                let other_collider = if contact_pair.collider1 == e.handle() {
-                    contact_pair.collider2
-                } else {
-                    contact_pair.collider1
-                };
-                let other_e = other_collider.entity();
-                if burn_src_query.get(other_e).is_err() {
-                    commands.entity(other_e).insert(burn_src.generate_effect());
-                }
-            }
-        }
+impl ContactEffect for Burned {
+    fn refresh(&mut self) {
+        self.duration.reset();
+    }
+
+    fn add_stack(&mut self, max: u32) {
+        self.stacks = (self.stacks + 1).min(max);
+        self.duration.reset();
     }
 }
 
 fn burned_system(
     mut ev_particle: EventWriter<ScatteringParticles>,
-    mut burned_query: Query<(&Transform, &mut Health, &mut Burned)>,
+    mut ev_damage: EventWriter<DamageEvent>,
+    burned_query: Query<(Entity, &Transform, &Burned), With<Health>>,
 ) {
-    for (pos, mut health, burned) in burned_query.iter_mut() {
+    for (e, pos, burned) in burned_query.iter() {
         if burned.interval.just_finished() {
-            health.hp -= burned.dmg;
+            ev_damage.send(DamageEvent {
+                target: e,
+                source: None,
+                amount: burned.dmg * burned.stacks as i32,
+                kind: DamageKind::Burn,
+            });
             ev_particle.send(ScatteringParticles {
                 pos: Vec3::new(pos.translation.x, pos.translation.y, 21.0),
                 num: 20,
@@ -299,26 +460,26 @@ fn burned_system(
     }
 }
 
-/// Apply `Paralyzed` upon hitting players but not upon hitting objects.
-#[derive(Component)]
-pub struct ParalyzeSource {
-    pub duration: f32,
-}
+/// Apply `Paralyzed` upon hitting the player and enemies but not upon hitting objects.
+/// They are immune for as long as the paralysis lasted once it ends.
+pub type ParalyzeSource = ContactEffectSource<Paralyzed>;
 
 impl ParalyzeSource {
     pub fn new(duration: f32) -> Self {
-        ParalyzeSource { duration }
-    }
-
-    pub fn generate_effect(&self) -> Paralyzed {
-        Paralyzed {
-            duration: Timer::from_seconds(self.duration, false),
+        ContactEffectSource {
+            effect: Paralyzed {
+                duration: Timer::from_seconds(duration, false),
+            },
+            targets: Targets::Characters,
+            immune: false,
+            stacking: Stacking::Ignore,
+            immunity: Some(duration),
         }
     }
 }
 
 /// Unable to move for some period if paralyzed.
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Paralyzed {
     duration: Timer,
 }
@@ -329,29 +490,14 @@ impl MagicWithTimer for Paralyzed {
     }
 }
 
-/// # Bug in Rapier: contact pairs sometimes contain despawned entities.Must check validity before use.
-fn paralyze_src_system(
-    mut commands: Commands,
-    narrow_phase: Res<NarrowPhase>,
-    paralyze_src_query: Query<(Entity, &ParalyzeSource)>,
-    player_query: Query<(), With<Player>>,
-) {
-    for (e, paralyze_src) in paralyze_src_query.iter() {
-        for contact_pair in narrow_phase.contacts_with(e.handle()) {
-            if contact_pair.has_any_active_contact {
-                let other_collider = if contact_pair.collider1 == e.handle() {
-                    contact_pair.collider2
-                } else {
-                    contact_pair.collider1
-                };
-                let other_e = other_collider.entity();
-                if player_query.get(other_e).is_ok() {
-                    commands
-                        .entity(other_e)
-                        .insert(paralyze_src.generate_effect());
-                }
-            }
-        }
+/// Paralysis has no intensity, a stack only restarts it.
+impl ContactEffect for Paralyzed {
+    fn refresh(&mut self) {
+        self.duration.reset();
+    }
+
+    fn add_stack(&mut self, _max: u32) {
+        self.refresh();
     }
 }
 
@@ -376,63 +522,396 @@ fn paralyzed_animation_system(
     }
 }
 
+/// The status effects that can react with each other.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum EffectKind {
+    Frozen,
+    Burned,
+    Paralyzed,
+}
+
+/// What happens when an effect is added to an entity that already has another one.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct EffectReaction {
+    pub cancel_new: bool,
+    pub cancel_existing: bool,
+    /// Damage dealt once to the entity.
+    pub dmg: i32,
+}
+
+/// Interaction table keyed by `(new effect, existing effect)`.
+/// Pairs without an entry don't react.
+pub struct EffectReactions(pub HashMap<(EffectKind, EffectKind), EffectReaction>);
+
+impl EffectReactions {
+    pub fn get(&self, new: EffectKind, existing: EffectKind) -> Option<&EffectReaction> {
+        self.0.get(&(new, existing))
+    }
+
+    pub fn insert(&mut self, new: EffectKind, existing: EffectKind, reaction: EffectReaction) {
+        self.0.insert((new, existing), reaction);
+    }
+}
+
+impl Default for EffectReactions {
+    fn default() -> Self {
+        use EffectKind::*;
+        let mut reactions = EffectReactions(HashMap::default());
+        // Fire thaws ice, and both are gone.
+        let thaw = EffectReaction {
+            cancel_new: true,
+            cancel_existing: true,
+            dmg: 0,
+        };
+        reactions.insert(Burned, Frozen, thaw);
+        reactions.insert(Frozen, Burned, thaw);
+        // Burning while unable to move hurts more.
+        let scorch = EffectReaction {
+            dmg: 5,
+            ..Default::default()
+        };
+        reactions.insert(Burned, Paralyzed, scorch);
+        reactions.insert(Paralyzed, Burned, scorch);
+        reactions
+    }
+}
+
+fn remove_effect(e: &mut EntityCommands, kind: EffectKind) {
+    match kind {
+        EffectKind::Frozen => {
+            e.remove::<Frozen>().remove::<ImmunityAfter<Frozen>>();
+        }
+        EffectKind::Burned => {
+            e.remove::<Burned>().remove::<ImmunityAfter<Burned>>();
+        }
+        EffectKind::Paralyzed => {
+            e.remove::<Paralyzed>().remove::<ImmunityAfter<Paralyzed>>();
+        }
+    }
+}
+
+/// Look up every pair made of a newly added effect and another effect on the same entity.
+/// When two effects are added in the same frame the pair only reacts once.
+fn effect_reaction_system(
+    mut commands: Commands,
+    mut ev_damage: EventWriter<DamageEvent>,
+    reactions: Res<EffectReactions>,
+    query: Query<
+        (
+            Entity,
+            Option<ChangeTrackers<Frozen>>,
+            Option<ChangeTrackers<Burned>>,
+            Option<ChangeTrackers<Paralyzed>>,
+        ),
+        Or<(Added<Frozen>, Added<Burned>, Added<Paralyzed>)>,
+    >,
+) {
+    for (e, frozen, burned, paralyzed) in query.iter() {
+        let effects: Vec<(EffectKind, bool)> = [
+            (EffectKind::Frozen, frozen.map(|t| t.is_added())),
+            (EffectKind::Burned, burned.map(|t| t.is_added())),
+            (EffectKind::Paralyzed, paralyzed.map(|t| t.is_added())),
+        ]
+        .iter()
+        .filter_map(|(kind, added)| added.map(|added| (*kind, added)))
+        .collect();
+
+        let mut cancelled = HashSet::default();
+        for &(new, new_added) in effects.iter() {
+            if !new_added {
+                continue;
+            }
+            for &(existing, existing_added) in effects.iter() {
+                if new == existing || (existing_added && existing < new) {
+                    continue;
+                }
+                let reaction = reactions.get(new, existing).or_else(|| {
+                    // Both are new, so either order applies.
+                    if existing_added {
+                        reactions.get(existing, new)
+                    } else {
+                        None
+                    }
+                });
+                if let Some(reaction) = reaction {
+                    if reaction.cancel_new {
+                        cancelled.insert(new);
+                    }
+                    if reaction.cancel_existing {
+                        cancelled.insert(existing);
+                    }
+                    if reaction.dmg != 0 {
+                        ev_damage.send(DamageEvent {
+                            target: e,
+                            source: None,
+                            amount: reaction.dmg,
+                            kind: DamageKind::Reaction,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut entity = commands.entity(e);
+        for kind in cancelled {
+            remove_effect(&mut entity, kind);
+        }
+    }
+}
+
+/// How an explosion's strength decreases from its center to the edge of its radius.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Falloff {
+    Constant,
+    Linear,
+    Quadratic,
+}
+
+impl Falloff {
+    /// `t` is the distance from the center divided by the radius.
+    pub fn scale(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
 /// Explode: deal `dmg` to all entities in a circle of `radius`
+/// and push rigid bodies away with an impulse of up to `force`, weakened by `falloff`.
+/// Lighter bodies are pushed further.
+/// Another explosion that would kill the holder sets it off `chain_delay` seconds later instead.
+/// A dead holder goes off on the next frame and is only despawned once this is spent.
 #[derive(Component)]
 pub struct Explode {
     pub radius: f32,
     pub dmg: i32,
+    pub force: f32,
+    pub falloff: Falloff,
+    pub chain_delay: f32,
 }
 
 impl Explode {
-    pub fn new(radius: f32, dmg: i32) -> Self {
-        Explode { radius, dmg }
+    pub fn new(radius: f32, dmg: i32, force: f32, falloff: Falloff, chain_delay: f32) -> Self {
+        Explode {
+            radius,
+            dmg,
+            force,
+            falloff,
+            chain_delay,
+        }
     }
 }
 
+/// Set off by another explosion, goes off once `fuse` is finished.
+/// Killing the holder meanwhile doesn't set it off any sooner.
+/// It then takes the `dmg` of the explosion `source` that set it off.
+#[derive(Component)]
+pub struct Detonating {
+    fuse: Timer,
+    source: Entity,
+    dmg: i32,
+}
+
+/// Explosions of a step go off in entity order, so cascades play out the same way every time.
+/// Each target is hit at most once per explosion.
+#[allow(clippy::too_many_arguments)]
 fn explode_system(
+    mut commands: Commands,
+    mut ev_damage: EventWriter<DamageEvent>,
+    time: Res<SimTime>,
     query_pipeline: Res<QueryPipeline>,
     mut ev_explosion: EventWriter<ExplodeParticles>,
     collider_query: QueryPipelineColliderComponentsQuery,
     explode_query: Query<(Entity, &Explode, &Transform)>,
-    mut health_query: Query<&mut Health>,
+    mut detonating_query: Query<(Entity, &mut Detonating)>,
+    health_query: Query<(&Health, Option<&Resistances>)>,
+    credit_query: Query<(), With<PlayerCredit>>,
     mut rigid_bodies: Query<(
         &RigidBodyPositionComponent,
         &mut RigidBodyVelocityComponent,
         &RigidBodyMassPropsComponent,
     )>,
 ) {
+    let mut due = vec![];
+    for (e, mut detonating) in detonating_query.iter_mut() {
+        if detonating.fuse.tick(time.delta()).just_finished() {
+            ev_damage.send(DamageEvent {
+                target: e,
+                source: Some(detonating.source),
+                amount: detonating.dmg,
+                kind: DamageKind::Explosion,
+            });
+            due.push(e);
+        }
+    }
+    for (e, _, _) in explode_query.iter() {
+        let dead = health_query.get(e).map_or(false, |(health, _)| health.hp <= 0);
+        if dead && detonating_query.get(e).is_err() {
+            due.push(e);
+        }
+    }
+    due.sort();
+
     let collider_set = QueryPipelineColliderComponentsSet(&collider_query);
-    for (explode_entity, explode, pos) in explode_query.iter() {
-        let health = health_query.get(explode_entity).unwrap();
-        if health.hp <= 0 {
-            // Animation
-            ev_explosion.send(ExplodeParticles::new(
-                Vec3::new(pos.translation.x, pos.translation.y, 25.0),
-                explode.radius,
-            ));
-            // Explosion on surrounding objects
-            let (explode_pos, _, _) = rigid_bodies.get(explode_entity).unwrap();
-            let explode_shape = ColliderShape::ball(explode.radius);
-            let groups = InteractionGroups::all();
-            let filter = None;
-            query_pipeline.intersections_with_shape(
-                &collider_set,
-                &explode_pos.position,
-                explode_shape.as_ref(),
-                groups,
-                filter,
-                |handle| {
-                    let e = handle.entity();
-                    let mut health = health_query.get_mut(e).unwrap();
-                    health.hp -= explode.dmg;
-                    // TODO: repulsion from explosion center
-                    // let (rb_pos, mut rb_vel, rb_mprops) = rigid_bodies.get_mut(e).unwrap();
-                    // let dist = rb_pos.position.translation.vector - explode_pos.position.translation.vector;
-                    // let impulse = Vec2::new(100.0 / dist.x, 100.0 / dist.y).into();
-                    // rb_vel.apply_impulse(rb_mprops, impulse);
-                    true
-                },
-            );
+    let mut armed = HashSet::default();
+    for explode_entity in due {
+        let (_, explode, pos) = explode_query.get(explode_entity).unwrap();
+        commands.entity(explode_entity).remove::<Explode>();
+        // Animation
+        ev_explosion.send(ExplodeParticles::new(
+            Vec3::new(pos.translation.x, pos.translation.y, 25.0),
+            explode.radius,
+        ));
+        // Explosion on surrounding objects
+        let center = rigid_bodies.get(explode_entity).unwrap().0.position;
+        let explode_shape = ColliderShape::ball(explode.radius);
+        let groups = InteractionGroups::all();
+        let filter = None;
+        let mut hit = HashSet::default();
+        query_pipeline.intersections_with_shape(
+            &collider_set,
+            &center,
+            explode_shape.as_ref(),
+            groups,
+            filter,
+            |handle| {
+                let e = handle.entity();
+                if e == explode_entity || !hit.insert(e) {
+                    return true;
+                }
+                let chained = explode_query.get(e).ok().map(|(_, chained, _)| chained);
+                let going_off = armed.contains(&e) || detonating_query.get(e).is_ok();
+                if let Ok((health, resistances)) = health_query.get(e) {
+                    let dmg = match resistances {
+                        Some(resistances) => resistances.reduce(DamageKind::Explosion, explode.dmg),
+                        None => explode.dmg,
+                    };
+                    match chained {
+                        // Already going off, the explosion is what's left of it
+                        Some(_) if going_off => {}
+                        Some(chained) if health.hp > 0 && health.hp <= dmg => {
+                            armed.insert(e);
+                            commands.entity(e).insert(Detonating {
+                                fuse: Timer::from_seconds(chained.chain_delay, false),
+                                source: explode_entity,
+                                dmg: explode.dmg,
+                            });
+                            if credit_query.get(explode_entity).is_ok() {
+                                commands.entity(e).insert(PlayerCredit);
+                            }
+                        }
+                        _ => ev_damage.send(DamageEvent {
+                            target: e,
+                            source: Some(explode_entity),
+                            amount: explode.dmg,
+                            kind: DamageKind::Explosion,
+                        }),
+                    }
+                }
+                // Repulsion from explosion center
+                if let Ok((rb_pos, mut rb_vel, rb_mprops)) = rigid_bodies.get_mut(e) {
+                    let offset: Vec2 = (rb_pos.position.translation.vector
+                        - center.translation.vector)
+                        .into();
+                    let dist = offset.length();
+                    if dist > f32::EPSILON {
+                        let strength = explode.force * explode.falloff.scale(dist / explode.radius);
+                        rb_vel.apply_impulse(rb_mprops, (offset / dist * strength).into());
+                    }
+                }
+                true
+            },
+        );
+    }
+}
+
+/// A holder killed by damage credited to the player goes off on the player's behalf.
+fn explode_credit_system(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    explode_query: Query<(), With<Explode>>,
+    credit_query: Query<(), With<PlayerCredit>>,
+) {
+    for ev in ev_death.iter() {
+        let credited = ev.source.map_or(false, |source| credit_query.get(source).is_ok());
+        if credited && explode_query.get(ev.target).is_ok() {
+            commands.entity(ev.target).insert(PlayerCredit);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape_mod::Type;
+    use crate::test_support::TestApp;
+
+    /// Spawn `id` overlapping the player, who stands at (0, -10), and let the contact start.
+    fn touch_player(id: Type) -> (TestApp, Entity) {
+        let mut app = TestApp::new();
+        let player = app.player();
+        app.spawn_object(id, [0.0, -7.0]);
+        app.steps(5);
+        (app, player)
+    }
+
+    #[test]
+    fn heart_freezes_on_contact() {
+        let (app, player) = touch_player(Type::Heart);
+        assert!(app.get::<Frozen>(player).is_some());
+    }
+
+    #[test]
+    fn triangle_burns_on_contact() {
+        let (app, player) = touch_player(Type::Triangle);
+        assert!(app.get::<Burned>(player).is_some());
+    }
+
+    #[test]
+    fn square_paralyzes_on_contact() {
+        let (app, player) = touch_player(Type::Square);
+        assert!(app.get::<Paralyzed>(player).is_some());
+    }
+
+    #[test]
+    fn enemy_holding_a_heart_is_healed() {
+        let mut app = TestApp::new();
+        let enemy = app.spawn_enemy(Vec2::new(40.0, 20.0));
+        let heart = app.spawn_object(Type::Heart, [48.0, 20.0]);
+        app.step();
+        app.damage(enemy, 10);
+        app.steps(40);
+        assert_eq!(app.get::<Grabbed>(heart).unwrap().0, enemy);
+        assert!(app.get::<Health>(enemy).unwrap().hp > 20);
+    }
+
+    #[test]
+    fn square_paralyzes_enemies_on_contact() {
+        let mut app = TestApp::new();
+        let enemy = app.spawn_enemy(Vec2::new(40.0, 20.0));
+        app.spawn_object(Type::Square, [43.0, 20.0]);
+        app.steps(5);
+        assert!(app.get::<Paralyzed>(enemy).is_some());
+    }
+    #[test]
+    fn detonating_object_killed_during_its_fuse_still_explodes() {
+        let mut app = TestApp::new();
+        let first = app.spawn_object(Type::Circle, [20.0, 20.0]);
+        let second = app.spawn_object(Type::Circle, [35.0, 20.0]);
+        // only in reach of the second explosion
+        let bystander = app.spawn_object(Type::Rect, [35.0, 40.0]);
+        app.step();
+        app.damage(first, 100);
+        app.steps(2);
+        assert!(app.get::<Detonating>(second).is_some());
+        app.damage(second, 100);
+        app.steps(2);
+        assert!(app.app.world.get_entity(second).is_some());
+        app.steps(15);
+        assert!(app.app.world.get_entity(bystander).is_none());
+        assert!(app.app.world.get_entity(second).is_none());
+    }
+}
@@ -1,23 +1,51 @@
+mod action;
 mod animation;
 mod bundle;
 mod camera;
 mod component;
+mod damage;
 mod end_game;
+mod enemy;
+mod fixed_step;
+mod headless;
 mod in_game;
+mod main_menu;
 mod magic;
+mod menu;
+mod object_def;
 mod particle;
+mod pause;
+mod recipe;
+mod replay;
+mod rng;
+mod score;
+mod settings;
 mod shape_mod;
+mod stats;
 mod synthesis;
+#[cfg(test)]
+mod test_support;
 mod ui;
 
 use bundle::*;
 use camera::*;
 use end_game::*;
 use in_game::*;
+use object_def::*;
 use particle::*;
 use shape_mod::*;
 
+use crate::action::ActionPlugin;
 use crate::animation::AnimationPlugin;
+use crate::fixed_step::FixedStepPlugin;
+use crate::main_menu::MainMenuPlugin;
+use crate::menu::MenuPlugin;
+use crate::pause::PausePlugin;
+use crate::replay::ReplayPlugin;
+use crate::rng::RngPlugin;
+use crate::score::ScorePlugin;
+use crate::settings::SettingsPlugin;
+use crate::stats::StatsPlugin;
 use bevy::asset::{HandleId, LoadState};
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
@@ -38,6 +66,10 @@ const OFFSET_HORIZONTAL: f32 = 50.0;
 const OFFSET_VERTICAL: f32 = 50.0;
 
 fn main() {
+    if let Some(minutes) = arg_value(headless::HEADLESS_FLAG) {
+        headless::run(&minutes);
+        return;
+    }
     let mut app = App::new();
     #[cfg(target_arch = "wasm32")]
     {
@@ -48,51 +80,115 @@ fn main() {
         // .add_plugin(FrameTimeDiagnosticsPlugin::default())
         .add_plugin(ShapePlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugin(FixedStepPlugin)
+        .add_plugin(RngPlugin)
+        .add_plugin(ActionPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(ObjectDefPlugin)
         .add_plugin(CameraPlugin)
         .add_plugin(ParticlePlugin)
         .add_plugin(AnimationPlugin)
         .add_plugin(InGamePlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(StatsPlugin)
         .add_plugin(EndGamePlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(MainMenuPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(PausePlugin)
         .add_state(AppState::Setup)
         .init_resource::<Msaa>()
         .init_resource::<SpriteHandles>()
+        .init_resource::<DataHandles>()
         .init_resource::<SpriteAtlasHandle>()
         .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(load_textures))
         .add_system_set(SystemSet::on_update(AppState::Setup).with_system(check_textures))
-        .add_system_set(SystemSet::on_exit(AppState::Setup).with_system(setup_game))
+        .add_system_set(
+            SystemSet::on_exit(AppState::Setup)
+                .with_system(setup_game)
+                .with_system(setup_arena),
+        )
         .run();
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 enum AppState {
     Setup,
+    MainMenu,
+    Settings,
     InGame,
+    /// Pushed on top of `InGame`.
+    Paused,
     EndGame,
 }
 
+/// The value following `flag` on the command line, as in `--flag value` or `--flag=value`.
+pub fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(flag)?.strip_prefix('=').map(str::to_owned)
+        }
+    })
+}
+
 #[derive(Default)]
 struct SpriteHandles {
     handles: Vec<HandleUntyped>,
 }
 
+/// Data assets (recipes, ...) that must finish loading, successfully or not, before leaving `Setup`.
+#[derive(Default)]
+pub struct DataHandles {
+    pub handles: Vec<HandleUntyped>,
+}
+
 #[derive(Default)]
 pub struct SpriteAtlasHandle {
     pub handle: Handle<TextureAtlas>,
     map: HashMap<HandleId, usize>,
 }
 
+impl SpriteAtlasHandle {
+    /// Index of the sprite at `path` in the atlas. A headless simulation has no atlas and draws
+    /// nothing, so every sprite is at 0 there.
+    pub fn index(&self, path: &str) -> usize {
+        if self.map.is_empty() {
+            return 0;
+        }
+        self.map[&path.into()]
+    }
+}
+
 fn load_textures(mut sprite_handles: ResMut<SpriteHandles>, asset_server: Res<AssetServer>) {
     sprite_handles.handles = asset_server.load_folder("sprites").unwrap();
 }
 
+/// Whether every data asset is done loading. A malformed data file fails to load;
+/// its plugin reports it and falls back on exit.
+fn data_settled(data_handles: &DataHandles, asset_server: &AssetServer) -> bool {
+    data_handles.handles.iter().all(|handle| {
+        matches!(
+            asset_server.get_load_state(handle),
+            LoadState::Loaded | LoadState::Failed
+        )
+    })
+}
+
 fn check_textures(
     mut app_state: ResMut<State<AppState>>,
     sprite_handles: ResMut<SpriteHandles>,
     mut sprite_atlas_handle: ResMut<SpriteAtlasHandle>,
+    data_handles: Res<DataHandles>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut textures: ResMut<Assets<Image>>,
 ) {
+    if !data_settled(&data_handles, &asset_server) {
+        return;
+    }
     if let LoadState::Loaded =
         asset_server.get_group_load_state(sprite_handles.handles.iter().map(|handle| handle.id))
     {
@@ -124,17 +220,11 @@ fn check_textures(
         //     ..Default::default()
         // });
 
-        let _ = app_state.set(AppState::InGame).unwrap();
+        let _ = app_state.set(AppState::MainMenu).unwrap();
     }
 }
 
-fn setup_game(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut config: ResMut<RapierConfiguration>,
-) {
-    config.gravity = Vec2::new(0.0, 0.0).into();
-    config.scale = RAPIER_TO_BEVY;
+fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn_bundle(SpriteBundle {
         transform: Transform {
             translation: Vec3::new(0.0, 0.0, 0.0),
@@ -143,6 +233,20 @@ fn setup_game(
         texture: asset_server.load("bg.png"),
         ..Default::default()
     });
+}
+
+/// Physics and the arena, shared with the headless simulation.
+fn setup_arena(
+    mut commands: Commands,
+    mut config: ResMut<RapierConfiguration>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+) {
+    config.gravity = Vec2::new(0.0, 0.0).into();
+    config.scale = RAPIER_TO_BEVY;
+    // physics only steps with the simulation, see `fixed_step`
+    config.timestep_mode = TimestepMode::FixedTimestep;
+    config.physics_pipeline_active = false;
+    integration_parameters.dt = TIME_STEP;
     spawn_boundary(&mut commands);
 
     commands.insert_resource(EntityInRange {
@@ -0,0 +1,96 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use super::AppState;
+use crate::component::*;
+use crate::menu::*;
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu).with_system(main_menu_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(despawn_main_menu));
+    }
+}
+
+fn mode_label(mode: GameMode) -> String {
+    format!("Mode: {:?}", mode)
+}
+
+fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, mode: Res<GameMode>) {
+    let title_font: Handle<Font> = asset_server.load("fonts/FiraSans-Black.ttf");
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..Default::default()
+        })
+        .insert(MainMenuUI {})
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(30.0)),
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    "Blade",
+                    TextStyle {
+                        font: title_font,
+                        font_size: 80.0,
+                        color: Color::ANTIQUE_WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            spawn_menu_button(parent, font.clone(), "Start", MenuButton::Start);
+            spawn_menu_button(parent, font.clone(), &mode_label(*mode), MenuButton::Mode);
+            spawn_menu_button(parent, font.clone(), "Settings", MenuButton::Settings);
+            spawn_menu_button(parent, font, "Quit", MenuButton::Quit);
+        });
+}
+
+fn main_menu_system(
+    mut mode: ResMut<GameMode>,
+    mut ev_menu: EventReader<MenuEvent>,
+    mut ev_exit: EventWriter<AppExit>,
+    mut app_state: ResMut<State<AppState>>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for MenuEvent(button) in ev_menu.iter() {
+        match button {
+            MenuButton::Start => {
+                let _ = app_state.set(AppState::InGame);
+            }
+            MenuButton::Mode => {
+                *mode = mode.next();
+                set_button_label(MenuButton::Mode, &mode_label(*mode), &buttons, &mut texts);
+            }
+            MenuButton::Settings => {
+                let _ = app_state.set(AppState::Settings);
+            }
+            MenuButton::Quit => ev_exit.send(AppExit),
+            _ => {}
+        }
+    }
+}
+
+fn despawn_main_menu(mut commands: Commands, queries: Query<Entity, With<MainMenuUI>>) {
+    for entity in queries.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::action::{Action, ActionState};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuEvent>()
+            .init_resource::<MenuFocus>()
+            .add_system(menu_button_system);
+    }
+}
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// What a menu button does. Each screen handles the buttons it shows.
+/// Keyboard and gamepad navigation go through the buttons on screen in the order of this enum.
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum MenuButton {
+    Start,
+    Mode,
+    Settings,
+    Rebind(Action),
+    Resume,
+    Restart,
+    Back,
+    Quit,
+}
+
+/// Sent when a menu button is clicked, or confirmed while focused.
+pub struct MenuEvent(pub MenuButton);
+
+/// The button that Confirm activates. Hovering a button with the mouse focuses it.
+#[derive(Default, Debug)]
+pub struct MenuFocus(pub Option<MenuButton>);
+
+pub fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    font: Handle<Font>,
+    label: &str,
+    button: MenuButton,
+) {
+    let size = Size::new(Val::Px(320.0), Val::Px(65.0));
+    spawn_sized_menu_button(parent, font, label, button, size, 40.0);
+}
+
+/// Like `spawn_menu_button`, for screens with many buttons.
+pub fn spawn_sized_menu_button(
+    parent: &mut ChildBuilder,
+    font: Handle<Font>,
+    label: &str,
+    button: MenuButton,
+    size: Size<Val>,
+    font_size: f32,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size,
+                margin: Rect::all(Val::Px(font_size / 4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: NORMAL_BUTTON.into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font,
+                        font_size,
+                        color: Color::ANTIQUE_WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+/// Replace the label of `button`, if it is on screen.
+pub fn set_button_label(
+    button: MenuButton,
+    label: &str,
+    buttons: &Query<(&MenuButton, &Children)>,
+    texts: &mut Query<&mut Text>,
+) {
+    for (_, children) in buttons.iter().filter(|(b, _)| **b == button) {
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = label.to_owned();
+            }
+        }
+    }
+}
+
+fn menu_button_system(
+    actions: Res<ActionState>,
+    mut focus: ResMut<MenuFocus>,
+    mut ev_menu: EventWriter<MenuEvent>,
+    clicked_query: Query<(&Interaction, &MenuButton), (Changed<Interaction>, With<Button>)>,
+    mut button_query: Query<(&Interaction, &MenuButton, &mut UiColor), With<Button>>,
+) {
+    let mut buttons: Vec<MenuButton> = button_query.iter().map(|(_, b, _)| *b).collect();
+    if buttons.is_empty() {
+        focus.0 = None;
+        return;
+    }
+    buttons.sort();
+
+    for (interaction, button) in clicked_query.iter() {
+        match interaction {
+            Interaction::Clicked => ev_menu.send(MenuEvent(*button)),
+            Interaction::Hovered => focus.0 = Some(*button),
+            Interaction::None => {}
+        }
+    }
+
+    // the first button is focused when a screen opens
+    let current = focus
+        .0
+        .and_then(|f| buttons.iter().position(|b| *b == f))
+        .unwrap_or(0);
+    let len = buttons.len();
+    focus.0 = Some(buttons[current]);
+    if actions.just_pressed(Action::MenuDown) {
+        focus.0 = Some(buttons[(current + 1) % len]);
+    } else if actions.just_pressed(Action::MenuUp) {
+        focus.0 = Some(buttons[(current + len - 1) % len]);
+    } else if actions.just_pressed(Action::Confirm) {
+        ev_menu.send(MenuEvent(buttons[current]));
+    }
+
+    for (interaction, button, mut color) in button_query.iter_mut() {
+        *color = match interaction {
+            Interaction::Clicked => PRESSED_BUTTON.into(),
+            _ if focus.0 == Some(*button) => HOVERED_BUTTON.into(),
+            Interaction::Hovered => HOVERED_BUTTON.into(),
+            Interaction::None => NORMAL_BUTTON.into(),
+        };
+    }
+}
@@ -0,0 +1,414 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::damage::{DamageKind, DEFAULT_IMPACT_THRESHOLD};
+use crate::magic::*;
+use crate::shape_mod::{Type, Usage};
+use crate::{AppState, DataHandles, SpriteAtlasHandle, RAPIER_TO_LYON};
+
+pub const OBJECTS_PATH: &str = "default.objects.ron";
+
+pub struct ObjectDefPlugin;
+
+impl Plugin for ObjectDefPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<ObjectDefsAsset>()
+            .init_asset_loader::<ObjectDefsAssetLoader>()
+            .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(load_object_defs))
+            .add_system_set(SystemSet::on_exit(AppState::Setup).with_system(setup_registry));
+    }
+}
+
+/// Collider of an object, in rapier units.
+#[derive(Deserialize, Clone, Debug)]
+pub enum ColliderDef {
+    Ball {
+        radius: f32,
+    },
+    Cuboid {
+        hx: f32,
+        hy: f32,
+    },
+    Triangle {
+        a: (f32, f32),
+        b: (f32, f32),
+        c: (f32, f32),
+    },
+    /// Sub-shapes translated by the given offset.
+    Compound(Vec<((f32, f32), ColliderDef)>),
+}
+
+impl ColliderDef {
+    pub fn shape(&self) -> ColliderShape {
+        match self {
+            ColliderDef::Ball { radius } => ColliderShape::ball(*radius),
+            ColliderDef::Cuboid { hx, hy } => ColliderShape::cuboid(*hx, *hy),
+            ColliderDef::Triangle { a, b, c } => ColliderShape::triangle(
+                point![a.0, a.1],
+                point![b.0, b.1],
+                point![c.0, c.1],
+            ),
+            ColliderDef::Compound(parts) => ColliderShape::compound(
+                parts
+                    .iter()
+                    .map(|((x, y), part)| (Isometry::translation(*x, *y), part.shape()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match self {
+            ColliderDef::Ball { radius } if *radius <= 0.0 => {
+                Err(format!("ball radius must be positive, got {}", radius))
+            }
+            ColliderDef::Cuboid { hx, hy } if *hx <= 0.0 || *hy <= 0.0 => Err(format!(
+                "cuboid half extents must be positive, got ({}, {})",
+                hx, hy
+            )),
+            ColliderDef::Compound(parts) if parts.is_empty() => {
+                Err("compound collider has no parts".to_owned())
+            }
+            ColliderDef::Compound(parts) => parts.iter().try_for_each(|(_, part)| part.check()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Magic components attached to an object when it spawns.
+/// Fields mirror the constructors in `magic.rs`.
+#[derive(Deserialize, Clone, Debug)]
+pub enum MagicDef {
+    Heal { hp: i32, interval: f32 },
+    Sight { scale: f32 },
+    Explode {
+        radius: f32,
+        dmg: i32,
+        force: f32,
+        falloff: Falloff,
+        chain_delay: f32,
+    },
+    FreezeSource { scale: f32, duration: f32 },
+    BurnSource { dmg: i32, duration: f32, interval: f32 },
+    ParalyzeSource { duration: f32 },
+}
+
+impl MagicDef {
+    pub fn insert(&self, e: &mut EntityCommands) {
+        match *self {
+            MagicDef::Heal { hp, interval } => e.insert(Heal::new(hp, interval)),
+            MagicDef::Sight { scale } => e.insert(Sight::new(scale)),
+            MagicDef::Explode {
+                radius,
+                dmg,
+                force,
+                falloff,
+                chain_delay,
+            } => e.insert(Explode::new(radius, dmg, force, falloff, chain_delay)),
+            MagicDef::FreezeSource { scale, duration } => {
+                e.insert(FreezeSource::new(scale, duration))
+            }
+            MagicDef::BurnSource {
+                dmg,
+                duration,
+                interval,
+            } => e.insert(BurnSource::new(dmg, duration, interval)),
+            MagicDef::ParalyzeSource { duration } => e.insert(ParalyzeSource::new(duration)),
+        };
+    }
+}
+
+/// Lyon geometry of a `VisualDef::Shape`.
+#[derive(Deserialize, Clone, Debug)]
+pub enum ShapeDef {
+    /// Half extents in rapier units, like `ColliderDef::Cuboid`.
+    Rectangle { hx: f32, hy: f32 },
+    /// An SVG path in pixels, centered on the origin.
+    Svg(String),
+}
+
+/// How an object is drawn. Colors are (hue, saturation, lightness).
+#[derive(Deserialize, Clone, Debug)]
+pub enum VisualDef {
+    /// A sprite from the texture atlas, e.g. `sprites/circle/color.png`.
+    Sprite {
+        path: String,
+        #[serde(default)]
+        shadow: Option<String>,
+    },
+    /// An outlined lyon shape. The outline is darkened when the object is in range.
+    Shape {
+        shape: ShapeDef,
+        fill: (f32, f32, f32),
+        outline: (f32, f32, f32),
+        stroke: f32,
+    },
+}
+
+/// Everything needed to spawn one `Type` of object.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ObjectDef {
+    pub collider: ColliderDef,
+    pub density: f32,
+    pub health: i32,
+    /// Multiplier of impact damage dealt to others, see `impact_damage`.
+    pub dmg: i32,
+    /// Contact impulse the object shrugs off.
+    #[serde(default = "default_impact_threshold")]
+    pub impact_threshold: f32,
+    pub visual: VisualDef,
+    /// Visual scale in the world. Storage icons are drawn at 60% of it.
+    pub scale: f32,
+    #[serde(default)]
+    pub magic: Vec<MagicDef>,
+    /// See `Resistances`.
+    #[serde(default)]
+    pub resistances: Vec<(DamageKind, f32)>,
+}
+
+fn default_impact_threshold() -> f32 {
+    DEFAULT_IMPACT_THRESHOLD
+}
+
+impl ObjectDef {
+    /// Insert the sprite or shape bundle, plus the shadow of a sprite in the world.
+    pub fn insert_visual(
+        &self,
+        e: &mut EntityCommands,
+        sprite_atlas_handle: &SpriteAtlasHandle,
+        usage: Usage,
+    ) {
+        let scale = match usage {
+            Usage::World => self.scale,
+            Usage::Storage => self.scale * 0.6,
+        };
+        match &self.visual {
+            VisualDef::Sprite { path, shadow } => {
+                e.insert_bundle(SpriteSheetBundle {
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, 5.0),
+                        scale: Vec3::new(scale, scale, 1.0),
+                        ..Default::default()
+                    },
+                    sprite: TextureAtlasSprite::new(sprite_atlas_handle.index(path)),
+                    texture_atlas: sprite_atlas_handle.handle.clone(),
+                    ..Default::default()
+                });
+                if let (Usage::World, Some(shadow)) = (usage, shadow) {
+                    e.with_children(|parent| {
+                        parent.spawn_bundle(SpriteSheetBundle {
+                            transform: Transform {
+                                translation: Vec3::new(0.0, -4.0, 6.0),
+                                ..Default::default()
+                            },
+                            sprite: TextureAtlasSprite::new(
+                                sprite_atlas_handle.index(shadow),
+                            ),
+                            texture_atlas: sprite_atlas_handle.handle.clone(),
+                            ..Default::default()
+                        });
+                    });
+                }
+            }
+            VisualDef::Shape {
+                shape,
+                fill,
+                outline,
+                stroke,
+            } => {
+                let draw_mode = DrawMode::Outlined {
+                    fill_mode: FillMode::color(Color::hsl(fill.0, fill.1, fill.2)),
+                    outline_mode: StrokeMode::new(
+                        Color::hsl(outline.0, outline.1, outline.2),
+                        *stroke,
+                    ),
+                };
+                let transform = Transform {
+                    translation: Vec3::new(0.0, 0.0, 10.0),
+                    scale: Vec3::new(scale, scale, 1.0),
+                    ..Default::default()
+                };
+                let bundle = match shape {
+                    ShapeDef::Rectangle { hx, hy } => GeometryBuilder::build_as(
+                        &shapes::Rectangle {
+                            extents: Vec2::new(*hx, *hy) * 2.0 * RAPIER_TO_LYON,
+                            origin: RectangleOrigin::Center,
+                        },
+                        draw_mode,
+                        transform,
+                    ),
+                    ShapeDef::Svg(svg_path_string) => GeometryBuilder::build_as(
+                        &shapes::SvgPathShape {
+                            svg_path_string: svg_path_string.clone(),
+                            svg_doc_size_in_px: Vec2::new(0., 0.),
+                        },
+                        draw_mode,
+                        transform,
+                    ),
+                };
+                e.insert_bundle(bundle);
+            }
+        }
+    }
+
+    fn sprite_paths(&self) -> Vec<&str> {
+        match &self.visual {
+            VisualDef::Sprite { path, shadow } => {
+                let mut paths = vec![path.as_str()];
+                paths.extend(shadow.as_deref());
+                paths
+            }
+            VisualDef::Shape { .. } => vec![],
+        }
+    }
+}
+
+/// Object definitions loaded from a `*.objects.ron` asset, keyed by `Type`.
+#[derive(Debug, TypeUuid)]
+#[uuid = "1fcc6296-7914-44b2-949e-2980615f6179"]
+pub struct ObjectDefsAsset {
+    pub defs: HashMap<Type, ObjectDef>,
+}
+
+/// The object definitions used by `CommandsSpawner::spawn_object`.
+pub struct ObjectRegistry(pub HashMap<Type, ObjectDef>);
+
+impl ObjectRegistry {
+    pub fn get(&self, id: Type) -> &ObjectDef {
+        self.0
+            .get(&id)
+            .unwrap_or_else(|| panic!("no object definition for {:?}", id))
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjectDefError {
+    Parse(ron::Error),
+    UnknownType(String),
+    Invalid { id: Type, reason: String },
+    Missing(Type),
+}
+
+impl fmt::Display for ObjectDefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjectDefError::Parse(e) => write!(f, "malformed object file: {}", e),
+            ObjectDefError::UnknownType(name) => write!(f, "unknown object type `{}`", name),
+            ObjectDefError::Invalid { id, reason } => write!(f, "object {:?}: {}", id, reason),
+            ObjectDefError::Missing(id) => write!(f, "object {:?} is not defined", id),
+        }
+    }
+}
+
+impl std::error::Error for ObjectDefError {}
+
+const DEFINED: &[Type] = &[
+    Type::Square,
+    Type::Circle,
+    Type::Rect,
+    Type::Triangle,
+    Type::Heart,
+    Type::Rust,
+];
+
+/// Parse and validate an object file. Every `Type` except `Empty` must be defined.
+pub fn parse_object_defs(bytes: &[u8]) -> Result<HashMap<Type, ObjectDef>, ObjectDefError> {
+    let raw: std::collections::HashMap<String, ObjectDef> =
+        ron::de::from_bytes(bytes).map_err(ObjectDefError::Parse)?;
+    let mut defs = HashMap::default();
+    for (name, def) in raw.into_iter() {
+        let id: Type = name
+            .parse()
+            .map_err(|_| ObjectDefError::UnknownType(name.clone()))?;
+        let invalid = |reason: String| ObjectDefError::Invalid { id, reason };
+        if id == Type::Empty {
+            return Err(invalid("`Empty` cannot be defined".to_owned()));
+        }
+        if def.density <= 0.0 {
+            return Err(invalid(format!(
+                "density must be positive, got {}",
+                def.density
+            )));
+        }
+        if def.scale <= 0.0 {
+            return Err(invalid(format!("scale must be positive, got {}", def.scale)));
+        }
+        def.collider.check().map_err(invalid)?;
+        if let VisualDef::Shape { stroke, .. } = def.visual {
+            if stroke < 0.0 {
+                return Err(invalid(format!("stroke must not be negative, got {}", stroke)));
+            }
+        }
+        defs.insert(id, def);
+    }
+    if let Some(&id) = DEFINED.iter().find(|id| !defs.contains_key(id)) {
+        return Err(ObjectDefError::Missing(id));
+    }
+    Ok(defs)
+}
+
+#[derive(Default)]
+pub struct ObjectDefsAssetLoader;
+
+impl AssetLoader for ObjectDefsAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let defs = parse_object_defs(bytes).map_err(|e| {
+                error!("{}: {}", load_context.path().display(), e);
+                e
+            })?;
+            load_context.set_default_asset(LoadedAsset::new(ObjectDefsAsset { defs }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["objects.ron"]
+    }
+}
+
+pub struct ObjectDefsHandle(pub Handle<ObjectDefsAsset>);
+
+fn load_object_defs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut data_handles: ResMut<DataHandles>,
+) {
+    let handle: Handle<ObjectDefsAsset> = asset_server.load(OBJECTS_PATH);
+    data_handles.handles.push(handle.clone_untyped());
+    commands.insert_resource(ObjectDefsHandle(handle));
+}
+
+/// Objects can't be spawned without definitions, so a failed load is fatal here.
+fn setup_registry(
+    mut commands: Commands,
+    handle: Res<ObjectDefsHandle>,
+    object_defs: Res<Assets<ObjectDefsAsset>>,
+    sprite_atlas_handle: Res<SpriteAtlasHandle>,
+) {
+    let defs = match object_defs.get(&handle.0) {
+        Some(asset) => asset.defs.clone(),
+        None => panic!("could not load object definitions from `{}`", OBJECTS_PATH),
+    };
+    // a headless simulation has no atlas to check against
+    let has_atlas = !sprite_atlas_handle.map.is_empty();
+    for (id, def) in defs.iter() {
+        for path in def.sprite_paths() {
+            if has_atlas && !sprite_atlas_handle.map.contains_key(&path.into()) {
+                panic!("object {:?}: sprite `{}` is not in the atlas", id, path);
+            }
+        }
+    }
+    commands.insert_resource(ObjectRegistry(defs));
+}
@@ -1,7 +1,10 @@
-use crate::RAPIER_TO_BEVY;
+use crate::component::RunScoped;
+use crate::rng::GameRng;
+use crate::{AppState, RAPIER_TO_BEVY};
+use bevy::ecs::schedule::ShouldRun;
 use bevy::ecs::system::Resource;
 use bevy::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::f32::consts::PI;
 
 // Reference: https://github.com/cvhariharan/smoke-rs
@@ -15,6 +18,7 @@ impl Plugin for ParticlePlugin {
             // .add_startup_system(setup_particles)
             .add_system_set(
                 SystemSet::new()
+                    .with_run_criteria(not_paused)
                     .with_system(spawn_particles::<ScatteringParticles>)
                     .with_system(spawn_particles::<ExplodeParticles>)
                     .with_system(update_positions)
@@ -25,11 +29,30 @@ impl Plugin for ParticlePlugin {
     }
 }
 
+/// Particles keep moving on every screen except the pause menu.
+fn not_paused(app_state: Res<State<AppState>>) -> ShouldRun {
+    if *app_state.current() == AppState::Paused {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+/// Particle speeds, fading and shrinking are given per frame at this frame rate,
+/// and scaled by the actual frame time.
+const PARTICLE_FPS: f32 = 60.0;
+/// Alpha lost per frame at `PARTICLE_FPS`, out of 255.
+const FADE: f32 = 3.0;
+
+fn frames(time: &Time) -> f32 {
+    time.delta_seconds() * PARTICLE_FPS
+}
+
 #[derive(Component)]
 struct Particle;
 
 #[derive(Component)]
-struct Lifetime(i32);
+struct Lifetime(f32);
 
 #[derive(Component)]
 struct ParticleVel(Vec3);
@@ -38,7 +61,8 @@ struct ParticleVel(Vec3);
 struct ParticleAcc(Vec3);
 
 pub trait ParticleEvent {
-    fn spawn(&self, commands: &mut Commands);
+    /// `rng` is the cosmetic stream of `GameRng`.
+    fn spawn<R: Rng>(&self, commands: &mut Commands, rng: &mut R);
 }
 
 #[derive(Component, Copy, Clone)]
@@ -63,8 +87,7 @@ impl Default for ScatteringParticles {
 }
 
 impl ParticleEvent for ScatteringParticles {
-    fn spawn(&self, commands: &mut Commands) {
-        let mut rng = thread_rng();
+    fn spawn<R: Rng>(&self, commands: &mut Commands, rng: &mut R) {
         for _ in 0..self.num {
             let dir = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
             commands
@@ -84,7 +107,8 @@ impl ParticleEvent for ScatteringParticles {
                     },
                     ..Default::default()
                 })
-                .insert(Lifetime(255))
+                .insert(Lifetime(255.0))
+                .insert(RunScoped)
                 .insert(ParticleVel(dir.clone() * self.vel_scale))
                 .insert(ParticleAcc(Vec3::ZERO));
         }
@@ -112,8 +136,7 @@ impl ExplodeParticles {
 }
 
 impl ParticleEvent for ExplodeParticles {
-    fn spawn(&self, commands: &mut Commands) {
-        let mut rng = thread_rng();
+    fn spawn<R: Rng>(&self, commands: &mut Commands, rng: &mut R) {
         for _ in 0..self.num {
             let r = rng.gen::<f32>().sqrt();
             let theta = rng.gen::<f32>() * 2.0 * PI;
@@ -134,7 +157,8 @@ impl ParticleEvent for ExplodeParticles {
                     },
                     ..Default::default()
                 })
-                .insert(Lifetime(255))
+                .insert(Lifetime(255.0))
+                .insert(RunScoped)
                 .insert(ParticleVel(dir.clone() * 2.0))
                 .insert(ParticleAcc(Vec3::ZERO))
                 .insert(self.scale_modifier);
@@ -142,34 +166,48 @@ impl ParticleEvent for ExplodeParticles {
     }
 }
 
-fn spawn_particles<T: ParticleEvent + Resource>(mut commands: Commands, mut ev: EventReader<T>) {
+fn spawn_particles<T: ParticleEvent + Resource>(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut ev: EventReader<T>,
+) {
     for ev in ev.iter() {
-        ev.spawn(&mut commands);
+        ev.spawn(&mut commands, &mut rng.cosmetic);
     }
 }
 
-fn update_positions(mut query: Query<(&mut Transform, &mut ParticleVel, &ParticleAcc)>) {
+fn update_positions(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut ParticleVel, &ParticleAcc)>,
+) {
+    let frames = frames(&time);
     for (mut pos, mut vel, acc) in query.iter_mut() {
-        vel.0 += acc.0;
-        pos.translation += vel.0;
+        vel.0 += acc.0 * frames;
+        pos.translation += vel.0 * frames;
     }
 }
 
-fn kill_particles(mut commands: Commands, mut query: Query<(Entity, &mut Lifetime, &mut Sprite)>) {
+fn kill_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Lifetime, &mut Sprite)>,
+) {
+    let frames = frames(&time);
     for (entity, mut lifetime, mut mode) in query.iter_mut() {
-        lifetime.0 -= 3;
-        if lifetime.0 <= 0 {
+        lifetime.0 -= FADE * frames;
+        if lifetime.0 <= 0.0 {
             commands.entity(entity).despawn();
         } else {
-            let alpha = lifetime.0 as f32 / 255.0;
+            let alpha = lifetime.0 / 255.0;
             mode.color.set_a(alpha);
         }
     }
 }
 
-fn scale_modifier_system(mut query: Query<(&mut Transform, &ScaleModifier)>) {
+fn scale_modifier_system(time: Res<Time>, mut query: Query<(&mut Transform, &ScaleModifier)>) {
+    let frames = frames(&time);
     for (mut transform, scale_modifier) in query.iter_mut() {
-        transform.scale -= scale_modifier.0;
+        transform.scale -= scale_modifier.0 * frames;
     }
 }
 
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use super::AppState;
+use crate::action::{clear_action_edges, Action, ActionState};
+use crate::component::*;
+use crate::menu::*;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(AppState::InGame).with_system(pause_input_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused)
+                    .with_system(enter_pause)
+                    .with_system(clear_action_edges),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_menu_system))
+            .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(exit_pause))
+            .add_system_set(SystemSet::on_resume(AppState::InGame).with_system(clear_action_edges));
+    }
+}
+
+/// `Paused` is pushed on top of `InGame`, so the systems of the run stop and resume
+/// where they were. Physics only steps with the run, see `fixed_step`.
+fn pause_input_system(actions: Res<ActionState>, mut app_state: ResMut<State<AppState>>) {
+    if actions.just_pressed(Action::Pause) {
+        let _ = app_state.push(AppState::Paused);
+    }
+}
+
+fn enter_pause(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..Default::default()
+        })
+        .insert(PauseUI {})
+        .with_children(|parent| {
+            spawn_menu_button(parent, font.clone(), "Resume", MenuButton::Resume);
+            spawn_menu_button(parent, font.clone(), "Restart", MenuButton::Restart);
+            spawn_menu_button(parent, font, "Quit", MenuButton::Quit);
+        });
+}
+
+fn pause_menu_system(
+    actions: Res<ActionState>,
+    mut ev_menu: EventReader<MenuEvent>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        let _ = app_state.pop();
+        return;
+    }
+    for MenuEvent(button) in ev_menu.iter() {
+        match button {
+            MenuButton::Resume => {
+                let _ = app_state.pop();
+            }
+            // entering `InGame` again starts a new run, see `teardown_run`
+            MenuButton::Restart => {
+                let _ = app_state.replace(AppState::InGame);
+            }
+            MenuButton::Quit => {
+                let _ = app_state.replace(AppState::MainMenu);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn exit_pause(mut commands: Commands, queries: Query<Entity, With<PauseUI>>) {
+    for entity in queries.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
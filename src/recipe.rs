@@ -0,0 +1,173 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::log::error;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::shape_mod::Type;
+use crate::synthesis::BLUEPRINT_SIZE;
+
+pub const RECIPES_PATH: &str = "default.recipes.ron";
+
+/// A recipe table loaded from a `*.recipes.ron` asset.
+/// Ingredient lists are merged and sorted by `Type`, the same key `Blueprint` produces.
+#[derive(Debug, TypeUuid)]
+#[uuid = "08a0fe2a-350e-43af-846b-40216a4b5cf4"]
+pub struct RecipeAsset {
+    pub recipes: Vec<(Vec<(Type, usize)>, Type)>,
+}
+
+/// One entry as written in the file, before item names are resolved.
+#[derive(Deserialize)]
+struct RawRecipe {
+    ingredients: Vec<(String, usize)>,
+    product: String,
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Parse(ron::Error),
+    UnknownType { entry: usize, name: String },
+    EmptyProduct { entry: usize },
+    NoIngredients { entry: usize, product: Type },
+    ZeroCount { entry: usize, product: Type, item: Type },
+    EmptyIngredient { entry: usize, product: Type },
+    TooManyIngredients { entry: usize, product: Type, total: usize },
+    Duplicate { entry: usize, product: Type, first: usize },
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use RecipeError::*;
+        match self {
+            Parse(e) => write!(f, "malformed recipe file: {}", e),
+            UnknownType { entry, name } => {
+                write!(f, "recipe #{}: unknown item type `{}`", entry, name)
+            }
+            EmptyProduct { entry } => write!(f, "recipe #{}: product cannot be `Empty`", entry),
+            NoIngredients { entry, product } => {
+                write!(f, "recipe #{} ({:?}): no ingredients", entry, product)
+            }
+            ZeroCount {
+                entry,
+                product,
+                item,
+            } => write!(
+                f,
+                "recipe #{} ({:?}): ingredient {:?} has a count of 0",
+                entry, product, item
+            ),
+            EmptyIngredient { entry, product } => write!(
+                f,
+                "recipe #{} ({:?}): `Empty` cannot be an ingredient",
+                entry, product
+            ),
+            TooManyIngredients {
+                entry,
+                product,
+                total,
+            } => write!(
+                f,
+                "recipe #{} ({:?}): needs {} ingredients but a blueprint holds {}",
+                entry, product, total, BLUEPRINT_SIZE
+            ),
+            Duplicate {
+                entry,
+                product,
+                first,
+            } => write!(
+                f,
+                "recipe #{} ({:?}): same ingredients as recipe #{}",
+                entry, product, first
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+fn parse_type(entry: usize, name: &str) -> Result<Type, RecipeError> {
+    name.parse().map_err(|_| RecipeError::UnknownType {
+        entry,
+        name: name.to_owned(),
+    })
+}
+
+/// Parse and validate a recipe file. Entries are numbered from 1 in error messages.
+pub fn parse_recipes(bytes: &[u8]) -> Result<Vec<(Vec<(Type, usize)>, Type)>, RecipeError> {
+    let raw: Vec<RawRecipe> = ron::de::from_bytes(bytes).map_err(RecipeError::Parse)?;
+    let mut recipes = vec![];
+    let mut seen: HashMap<Vec<(Type, usize)>, usize> = HashMap::default();
+    for (i, raw) in raw.into_iter().enumerate() {
+        let entry = i + 1;
+        let product = parse_type(entry, &raw.product)?;
+        if product == Type::Empty {
+            return Err(RecipeError::EmptyProduct { entry });
+        }
+        if raw.ingredients.is_empty() {
+            return Err(RecipeError::NoIngredients { entry, product });
+        }
+
+        let mut counts: HashMap<Type, usize> = HashMap::default();
+        for (name, count) in raw.ingredients.iter() {
+            let item = parse_type(entry, name)?;
+            if item == Type::Empty {
+                return Err(RecipeError::EmptyIngredient { entry, product });
+            }
+            if *count == 0 {
+                return Err(RecipeError::ZeroCount {
+                    entry,
+                    product,
+                    item,
+                });
+            }
+            *counts.entry(item).or_default() += count;
+        }
+        let total = counts.values().sum();
+        if total > BLUEPRINT_SIZE {
+            return Err(RecipeError::TooManyIngredients {
+                entry,
+                product,
+                total,
+            });
+        }
+
+        let mut ingredients: Vec<(Type, usize)> = counts.into_iter().collect();
+        ingredients.sort();
+        if let Some(&first) = seen.get(&ingredients) {
+            return Err(RecipeError::Duplicate {
+                entry,
+                product,
+                first,
+            });
+        }
+        seen.insert(ingredients.clone(), entry);
+        recipes.push((ingredients, product));
+    }
+    Ok(recipes)
+}
+
+#[derive(Default)]
+pub struct RecipeAssetLoader;
+
+impl AssetLoader for RecipeAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let recipes = parse_recipes(bytes).map_err(|e| {
+                error!("{}: {}", load_context.path().display(), e);
+                e
+            })?;
+            load_context.set_default_asset(LoadedAsset::new(RecipeAsset { recipes }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["recipes.ron"]
+    }
+}
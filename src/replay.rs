@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::{arg_value, AppState};
+use crate::action::{Action, ActionFrame, StepActions};
+use crate::component::GameMode;
+use crate::fixed_step::{FIXED_UPDATE, STEP_BEGIN};
+use crate::rng::GameRng;
+use crate::score::user_data_path;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let playback = arg_value(REPLAY_FLAG)
+            .and_then(|path| Replay::load(&path))
+            .map(Playback::new);
+        app.init_resource::<Recorder>()
+            .insert_resource(playback)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                replay_input_system.after("action_state"),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(start_recording))
+            .stage(FIXED_UPDATE, |step: &mut Schedule| {
+                step.add_system_to_stage(STEP_BEGIN, replay_step_system.after("begin_step"))
+            })
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(save_recording));
+    }
+}
+
+/// `--replay path` on the command line plays the replay at `path` instead of the main menu.
+pub const REPLAY_FLAG: &str = "--replay";
+/// Every run is recorded here, overwriting the previous one.
+pub const LAST_REPLAY_FILE: &str = "last.replay";
+const REPLAY_VERSION: u32 = 2;
+
+/// The inputs of a run, one frame per simulation step.
+/// Runs of identical input frames are stored once with their length.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Replay {
+    version: u32,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub frames: Vec<(u32, ActionFrame)>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Option<Self> {
+        let result = fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| {
+                bincode::deserialize::<Replay>(&bytes).map_err(anyhow::Error::from)
+            });
+        match result {
+            Ok(replay) if replay.version == REPLAY_VERSION => Some(replay),
+            Ok(replay) => {
+                error!(
+                    "{}: replay version {} is not supported, expected {}",
+                    path, replay.version, REPLAY_VERSION
+                );
+                None
+            }
+            Err(e) => {
+                error!("could not load replay {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct Recorder {
+    steps: usize,
+    frames: Vec<(u32, ActionFrame)>,
+}
+
+impl Recorder {
+    fn push(&mut self, mut frame: ActionFrame) {
+        // pausing is not part of the run
+        frame.pressed.retain(|action| *action != Action::Pause);
+        frame.just_pressed.retain(|action| *action != Action::Pause);
+        frame.just_released.retain(|action| *action != Action::Pause);
+        self.steps += 1;
+        match self.frames.last_mut() {
+            Some((count, last)) if *last == frame => *count += 1,
+            _ => self.frames.push((1, frame)),
+        }
+    }
+}
+
+/// A replay being played. Frames are unpacked on load.
+struct Playback {
+    seed: u64,
+    mode: GameMode,
+    frames: Vec<ActionFrame>,
+    /// The frame fed to the next step.
+    next: usize,
+    started: bool,
+    /// The seed setting and mode the replay replaced, restored once it is over.
+    live: Option<(Option<u64>, GameMode)>,
+}
+
+impl Playback {
+    fn new(replay: Replay) -> Self {
+        let frames = replay
+            .frames
+            .into_iter()
+            .flat_map(|(count, frame)| std::iter::repeat(frame).take(count as usize))
+            .collect();
+        Playback {
+            seed: replay.seed,
+            mode: replay.mode,
+            frames,
+            next: 0,
+            started: false,
+            live: None,
+        }
+    }
+}
+
+/// Starts a replay from the main menu with the recorded seed and mode,
+/// and leaves the run once it is over.
+fn replay_input_system(
+    mut app_state: ResMut<State<AppState>>,
+    mut rng: ResMut<GameRng>,
+    mut mode: ResMut<GameMode>,
+    mut playback: ResMut<Option<Playback>>,
+) {
+    if let Some(playback) = &mut *playback {
+        let finished = playback.next >= playback.frames.len();
+        match app_state.current() {
+            // a recording that ends without dying was quit from the pause menu
+            AppState::InGame if playback.started && finished => {
+                let _ = app_state.set(AppState::MainMenu);
+            }
+            AppState::MainMenu if !playback.started => {
+                info!("playing a replay of {} steps", playback.frames.len());
+                let live_seed = rng.replace_seed(Some(playback.seed));
+                playback.live = Some((live_seed, std::mem::replace(&mut *mode, playback.mode)));
+                playback.started = app_state.set(AppState::InGame).is_ok();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Records the input of every step, or replaces it with the recorded one while a replay plays.
+/// Past the end of the replay, the player stands still until the run is left.
+fn replay_step_system(
+    mut actions: ResMut<StepActions>,
+    mut recorder: ResMut<Recorder>,
+    mut playback: ResMut<Option<Playback>>,
+) {
+    match &mut *playback {
+        Some(playback) => {
+            let frame = playback.frames.get(playback.next).cloned().unwrap_or_default();
+            actions.apply_frame(&frame);
+            playback.next = (playback.next + 1).min(playback.frames.len());
+        }
+        None => recorder.push(actions.frame()),
+    }
+}
+
+fn start_recording(mut recorder: ResMut<Recorder>) {
+    *recorder = Recorder::default();
+}
+
+fn save_recording(
+    mut rng: ResMut<GameRng>,
+    mut mode: ResMut<GameMode>,
+    mut recorder: ResMut<Recorder>,
+    mut playback: ResMut<Option<Playback>>,
+) {
+    // once a replay is over, the game goes back to live input and its own settings
+    if let Some(replay) = &*playback {
+        if let Some((live_seed, live_mode)) = replay.live {
+            rng.replace_seed(live_seed);
+            *mode = live_mode;
+        }
+        let remaining = replay.frames.len().saturating_sub(replay.next);
+        if remaining == 0 {
+            info!("replay finished");
+        } else {
+            warn!(
+                "the replayed run ended {} steps early, it no longer matches the game",
+                remaining
+            );
+        }
+        *playback = None;
+        return;
+    }
+    if recorder.steps == 0 {
+        return;
+    }
+    let recorder = std::mem::take(&mut *recorder);
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        seed: rng.run_seed,
+        mode: *mode,
+        frames: recorder.frames,
+    };
+    let path = match user_data_path(LAST_REPLAY_FILE) {
+        Some(path) => path,
+        None => return,
+    };
+    let result = bincode::serialize(&replay)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, bytes).map_err(anyhow::Error::from)
+        });
+    match result {
+        Ok(()) => info!(
+            "replay of {} steps saved to {}",
+            recorder.steps,
+            path.display()
+        ),
+        Err(e) => error!("could not save replay to {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use std::fs;
+
+use super::{arg_value, AppState};
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = seed_arg().or_else(|| load_config(CONFIG_PATH).seed);
+        app.insert_resource(GameRng::new(seed))
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(start_run_rng));
+    }
+}
+
+pub const CONFIG_PATH: &str = "config.ron";
+/// `--seed 1234` on the command line fixes the seed of every run.
+pub const SEED_FLAG: &str = "--seed";
+
+/// Optional settings read from `CONFIG_PATH`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+struct Config {
+    /// Seed of every run, overridden by `SEED_FLAG`.
+    seed: Option<u64>,
+}
+
+/// A missing file is the default config, a malformed one is reported and ignored.
+fn load_config(path: &str) -> Config {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => return Config::default(),
+    };
+    match ron::de::from_str(&text) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}: {}, ignoring it", path, e);
+            Config::default()
+        }
+    }
+}
+
+fn seed_arg() -> Option<u64> {
+    let value = arg_value(SEED_FLAG)?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(e) => {
+            error!("{} {}: {}, using a random seed", SEED_FLAG, value, e);
+            None
+        }
+    }
+}
+
+/// Stream of `ChaCha8Rng` for each use, so drawing from one never shifts another.
+const GAMEPLAY_STREAM: u64 = 0;
+const COSMETIC_STREAM: u64 = 1;
+/// Inputs of the bot of a headless simulation, see `headless`.
+pub const BOT_STREAM: u64 = 2;
+
+/// All randomness of the game. Both streams restart from `run_seed` at the start of every run,
+/// so a run is reproduced by its seed.
+pub struct GameRng {
+    /// Seed given on the command line or in the config, if any.
+    seed: Option<u64>,
+    /// Seed of the current run: `seed`, or a fresh one for every run.
+    pub run_seed: u64,
+    /// Anything that can change the outcome of a run.
+    pub gameplay: ChaCha8Rng,
+    /// Particles and other effects. Must never feed back into gameplay.
+    pub cosmetic: ChaCha8Rng,
+}
+
+pub fn stream(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+impl GameRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut rng = GameRng {
+            seed,
+            run_seed: 0,
+            gameplay: stream(0, GAMEPLAY_STREAM),
+            cosmetic: stream(0, COSMETIC_STREAM),
+        };
+        rng.start_run();
+        rng
+    }
+
+    /// Seed every following run with `seed`, or with a fresh seed each if `None`.
+    /// Returns the previous setting.
+    pub fn replace_seed(&mut self, seed: Option<u64>) -> Option<u64> {
+        std::mem::replace(&mut self.seed, seed)
+    }
+
+    pub fn start_run(&mut self) {
+        self.run_seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.gameplay = stream(self.run_seed, GAMEPLAY_STREAM);
+        self.cosmetic = stream(self.run_seed, COSMETIC_STREAM);
+    }
+}
+
+fn start_run_rng(mut rng: ResMut<GameRng>) {
+    rng.start_run();
+    info!("run seed: {}", rng.run_seed);
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use super::AppState;
+use crate::bundle::Undead;
+use crate::component::{Player, PlayerCredit};
+use crate::damage::DeathEvent;
+use crate::fixed_step::{AddStepSystems, SimTime};
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .insert_resource(HighScores::load())
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_score))
+            .add_step_system_set(
+                SystemSet::new()
+                    .with_system(survival_time_system)
+                    .with_system(destroyed_system.after("apply_damage")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::EndGame)
+                    .with_system(record_score.label("record_score")),
+            );
+    }
+}
+
+pub const DESTROYED_POINTS: u32 = 10;
+/// Points for a synthesized item, per synthesis step needed to make it from basic items.
+pub const SYNTHESIS_POINTS: u32 = 25;
+/// Points per second survived.
+pub const SURVIVAL_POINTS: u32 = 1;
+
+/// Number of scores kept in the high score table.
+pub const HIGH_SCORE_COUNT: usize = 10;
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+/// What the current run has achieved so far.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct Score {
+    pub destroyed: u32,
+    pub synthesized: u32,
+    pub synthesis_points: u32,
+    /// Seconds survived, not counting pauses.
+    pub time: f32,
+}
+
+impl Score {
+    /// Count an item synthesized `depth` steps away from basic items, see `TableInverse::depth`.
+    pub fn add_synthesized(&mut self, depth: u32) {
+        self.synthesized += 1;
+        self.synthesis_points += SYNTHESIS_POINTS * depth;
+    }
+
+    pub fn total(&self) -> u32 {
+        self.destroyed * DESTROYED_POINTS
+            + self.synthesis_points
+            + self.time as u32 * SURVIVAL_POINTS
+    }
+}
+
+/// `total` is kept as it was scored, so changing the weights doesn't reorder old entries.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct HighScore {
+    pub total: u32,
+    pub score: Score,
+}
+
+/// The best scores, highest first, saved in the user data directory.
+/// The default table has no file and is never saved, as in headless simulations.
+#[derive(Default, Debug)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    /// Rank of the run that just ended, if it made it into the table.
+    pub last: Option<usize>,
+    path: Option<PathBuf>,
+}
+
+/// Where `file` is kept in the user data directory, if the platform has one.
+pub fn user_data_path(file: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("blade").join(file))
+}
+
+impl HighScores {
+    /// A missing or malformed file starts an empty table.
+    pub fn load() -> Self {
+        let path = match user_data_path(HIGH_SCORES_FILE) {
+            Some(path) => path,
+            None => return HighScores::default(),
+        };
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => match ron::de::from_str::<Vec<HighScore>>(&text) {
+                Ok(mut entries) => {
+                    entries.sort_by(|a, b| b.total.cmp(&a.total));
+                    entries.truncate(HIGH_SCORE_COUNT);
+                    entries
+                }
+                Err(e) => {
+                    error!("{}: {}, starting a new high score table", path.display(), e);
+                    vec![]
+                }
+            },
+            Err(_) => vec![],
+        };
+        HighScores {
+            entries,
+            last: None,
+            path: Some(path),
+        }
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let result = ron::ser::to_string_pretty(&self.entries, Default::default())
+            .map_err(anyhow::Error::from)
+            .and_then(|text| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(path, text).map_err(anyhow::Error::from)
+            });
+        if let Err(e) = result {
+            error!("could not save high scores to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Insert `score` at its rank. Ties rank below the older scores.
+    pub fn insert(&mut self, score: Score) {
+        let total = score.total();
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| total > entry.total)
+            .unwrap_or(self.entries.len());
+        if rank < HIGH_SCORE_COUNT {
+            self.entries.insert(rank, HighScore { total, score });
+            self.entries.truncate(HIGH_SCORE_COUNT);
+            self.last = Some(rank);
+        } else {
+            self.last = None;
+        }
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+/// Runs with the simulation, so time spent paused doesn't count.
+fn survival_time_system(time: Res<SimTime>, mut score: ResMut<Score>) {
+    score.time += time.delta_seconds();
+}
+
+/// Counts the deaths the player is credited with, see `PlayerCredit`.
+fn destroyed_system(
+    mut score: ResMut<Score>,
+    mut ev_death: EventReader<DeathEvent>,
+    destroyable_query: Query<(), (Without<Player>, Without<Undead>)>,
+    credit_query: Query<(), With<PlayerCredit>>,
+) {
+    for ev in ev_death.iter() {
+        let credited = ev.source.map_or(false, |source| credit_query.get(source).is_ok());
+        if credited && destroyable_query.get(ev.target).is_ok() {
+            score.destroyed += 1;
+        }
+    }
+}
+
+fn record_score(score: Res<Score>, mut high_scores: ResMut<HighScores>) {
+    high_scores.insert(*score);
+    high_scores.save();
+}
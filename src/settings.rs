@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use super::AppState;
+use crate::action::{Action, ActionMap, ActionState, Binding, Rebind};
+use crate::component::*;
+use crate::menu::*;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Settings).with_system(spawn_settings))
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    .with_system(settings_menu_system)
+                    .with_system(binding_label_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(exit_settings));
+    }
+}
+
+/// Actions listed on the settings screen, in two columns.
+/// The storage slots keep the number keys.
+const REBINDABLE: [&[Action]; 2] = [
+    &[
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Grab,
+        Action::Throw,
+        Action::Store,
+        Action::Hold,
+        Action::Synthesize,
+    ],
+    &[
+        Action::Clear,
+        Action::NextSlot,
+        Action::PrevSlot,
+        Action::AddToBlueprint,
+        Action::Confirm,
+        Action::Pause,
+        Action::MenuUp,
+        Action::MenuDown,
+    ],
+];
+
+fn binding_name(binding: &Binding) -> String {
+    match binding {
+        Binding::Key(key) => format!("{:?}", key),
+        Binding::Mouse(button) => format!("Mouse {:?}", button),
+        Binding::Gamepad(button) => format!("Pad {:?}", button),
+    }
+}
+
+/// A refused binding is shown in place of the action's bindings until the next rebind.
+fn binding_label(action: Action, map: &ActionMap, rebind: &Rebind) -> String {
+    if rebind.action == Some(action) {
+        return format!("{:?}: press a key...", action);
+    }
+    if let Some(refused) = rebind.refused.filter(|refused| refused.action == action) {
+        let binding = binding_name(&refused.binding);
+        return format!("{:?}: {} is taken by {:?}", action, binding, refused.used_by);
+    }
+    let bindings: Vec<String> = map.bindings(action).iter().map(binding_name).collect();
+    format!("{:?}: {}", action, bindings.join(", "))
+}
+
+fn spawn_settings(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    map: Res<ActionMap>,
+    rebind: Res<Rebind>,
+) {
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let size = Size::new(Val::Px(520.0), Val::Px(36.0));
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..Default::default()
+        })
+        .insert(SettingsUI {})
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for column in REBINDABLE.iter() {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::ColumnReverse,
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                for action in column.iter() {
+                                    spawn_sized_menu_button(
+                                        parent,
+                                        font.clone(),
+                                        &binding_label(*action, &map, &rebind),
+                                        MenuButton::Rebind(*action),
+                                        size,
+                                        22.0,
+                                    );
+                                }
+                            });
+                    }
+                });
+            spawn_menu_button(parent, font, "Back", MenuButton::Back);
+        });
+}
+
+/// Clicking an action waits for a key or mouse button to bind to it, see `Rebind`.
+fn settings_menu_system(
+    actions: Res<ActionState>,
+    mut rebind: ResMut<Rebind>,
+    mut ev_menu: EventReader<MenuEvent>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        let _ = app_state.set(AppState::MainMenu);
+        return;
+    }
+    for MenuEvent(button) in ev_menu.iter() {
+        match button {
+            MenuButton::Rebind(action) => {
+                rebind.action = Some(*action);
+                rebind.refused = None;
+            }
+            MenuButton::Back => {
+                let _ = app_state.set(AppState::MainMenu);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn binding_label_system(
+    map: Res<ActionMap>,
+    rebind: Res<Rebind>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !map.is_changed() && !rebind.is_changed() {
+        return;
+    }
+    for action in REBINDABLE.iter().flat_map(|column| column.iter()) {
+        let label = binding_label(*action, &map, &rebind);
+        set_button_label(MenuButton::Rebind(*action), &label, &buttons, &mut texts);
+    }
+}
+
+fn exit_settings(
+    mut commands: Commands,
+    mut rebind: ResMut<Rebind>,
+    queries: Query<Entity, With<SettingsUI>>,
+) {
+    rebind.action = None;
+    rebind.refused = None;
+    for entity in queries.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::AppState;
+use crate::component::*;
+use crate::damage::{DamageEvent, DamageKind, Resistances};
+use crate::fixed_step::{AddStepSystems, SimTime};
+use crate::magic::{Frozen, Paralyzed};
+use crate::rng::GameRng;
+use crate::score::{user_data_path, Score};
+use crate::shape_mod::Type;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .insert_resource(StatsExport(
+                std::env::args().any(|arg| arg == EXPORT_STATS_FLAG),
+            ))
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_stats))
+            .add_step_system_set(
+                SystemSet::new()
+                    .with_system(
+                        damage_stats_system
+                            .after("apply_damage")
+                            .before("despawn_dead_entities"),
+                    )
+                    .with_system(status_time_system),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::EndGame).with_system(export_stats));
+    }
+}
+
+/// Pass this on the command line to write the stats of every run as JSON,
+/// next to the high score table.
+pub const EXPORT_STATS_FLAG: &str = "--export-stats";
+
+pub struct StatsExport(pub bool);
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Hit {
+    pub kind: DamageKind,
+    /// Item that dealt the damage, if it is still around to tell.
+    pub source: Option<Type>,
+}
+
+/// What happened during the current run, for the EndGame screen and play session analysis.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct RunStats {
+    /// Damage the player dealt to anything else, after resistances, see `PlayerCredit`.
+    pub damage_dealt: BTreeMap<DamageKind, i32>,
+    /// Damage taken by the player, after resistances.
+    pub damage_taken: BTreeMap<DamageKind, i32>,
+    pub thrown: u32,
+    pub stored: u32,
+    /// Syntheses by product.
+    pub syntheses: BTreeMap<Type, u32>,
+    /// Seconds the player spent paralyzed.
+    pub paralyzed_time: f32,
+    /// Seconds the player spent frozen.
+    pub frozen_time: f32,
+    /// The last hit the player took, which is what killed them once the run is over.
+    pub cause_of_death: Option<Hit>,
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+/// Counts damage as `apply_damage_system` applies it: the player takes none in practice.
+fn damage_stats_system(
+    mode: Res<GameMode>,
+    mut stats: ResMut<RunStats>,
+    mut ev_damage: EventReader<DamageEvent>,
+    target_query: Query<(Option<&Resistances>, Option<&Player>)>,
+    source_query: Query<&Throwable>,
+    credit_query: Query<(), With<PlayerCredit>>,
+) {
+    for ev in ev_damage.iter() {
+        if let Ok((resistances, player)) = target_query.get(ev.target) {
+            if player.is_some() && *mode == GameMode::Practice {
+                continue;
+            }
+            let amount = ev.resisted(resistances);
+            if amount <= 0 {
+                continue;
+            }
+            if player.is_some() {
+                *stats.damage_taken.entry(ev.kind).or_default() += amount;
+                let source = ev
+                    .source
+                    .and_then(|source| source_query.get(source).ok())
+                    .map(|throwable| throwable.0);
+                stats.cause_of_death = Some(Hit {
+                    kind: ev.kind,
+                    source,
+                });
+            } else if ev.source.map_or(false, |source| credit_query.get(source).is_ok()) {
+                *stats.damage_dealt.entry(ev.kind).or_default() += amount;
+            }
+        }
+    }
+}
+
+fn status_time_system(
+    time: Res<SimTime>,
+    mut stats: ResMut<RunStats>,
+    player_query: Query<(Option<&Frozen>, Option<&Paralyzed>), With<Player>>,
+) {
+    for (frozen, paralyzed) in player_query.iter() {
+        if frozen.is_some() {
+            stats.frozen_time += time.delta_seconds();
+        }
+        if paralyzed.is_some() {
+            stats.paralyzed_time += time.delta_seconds();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RunReport<'a> {
+    /// Seconds since the Unix epoch when the run ended.
+    ended: u64,
+    /// Pass it with `--seed` to replay the run's spawns.
+    seed: u64,
+    total: u32,
+    score: &'a Score,
+    stats: &'a RunStats,
+}
+
+fn export_stats(
+    export: Res<StatsExport>,
+    rng: Res<GameRng>,
+    score: Res<Score>,
+    stats: Res<RunStats>,
+) {
+    if !export.0 {
+        return;
+    }
+    let ended = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let path = match user_data_path(&format!("run-{}.json", ended)) {
+        Some(path) => path,
+        None => return,
+    };
+    let report = RunReport {
+        ended,
+        seed: rng.run_seed,
+        total: score.total(),
+        score: &*score,
+        stats: &*stats,
+    };
+    let result = serde_json::to_string_pretty(&report)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, text).map_err(anyhow::Error::from)
+        });
+    match result {
+        Ok(()) => info!("run stats written to {}", path.display()),
+        Err(e) => error!("could not write run stats to {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    #[test]
+    fn practice_records_no_damage_taken() {
+        let mut app = TestApp::new();
+        *app.resource_mut::<GameMode>() = GameMode::Practice;
+        let player = app.player();
+        app.damage(player, 10);
+        app.step();
+        let stats = app.resource::<RunStats>();
+        assert!(stats.damage_taken.is_empty());
+        assert!(stats.cause_of_death.is_none());
+    }
+}
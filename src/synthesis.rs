@@ -3,13 +3,19 @@ use bevy_rapier2d::physics::RigidBodyComponentsQueryPayload;
 use bevy_rapier2d::prelude::*;
 
 use super::AppState;
+use crate::action::{Action, Device, StepActions};
 use crate::component::*;
-use crate::in_game::EntityInHand;
+use crate::fixed_step::AddStepSystems;
+use crate::in_game::{grab_object, EntityInHand};
+use crate::object_def::ObjectRegistry;
 use crate::shape_mod::*;
 use crate::ui::*;
 
 use crate::bundle::CommandsSpawner;
-use crate::SpriteAtlasHandle;
+use crate::recipe::*;
+use crate::score::Score;
+use crate::stats::RunStats;
+use crate::{DataHandles, SpriteAtlasHandle};
 use bevy::utils::HashMap;
 
 pub const STORAGE_SIZE: usize = 8;
@@ -20,10 +26,13 @@ pub struct SynthesisPlugin;
 impl Plugin for SynthesisPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(UIPlugin)
+            .add_asset::<RecipeAsset>()
+            .init_asset_loader::<RecipeAssetLoader>()
             .init_resource::<StorageInHand>()
-            .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(setup_table))
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
+            .add_system_set(SystemSet::on_enter(AppState::Setup).with_system(load_recipes))
+            .add_system_set(SystemSet::on_exit(AppState::Setup).with_system(setup_table))
+            .add_step_system_set(
+                SystemSet::new()
                     // .with_system(set_recipe_global_transform)
                     .with_system(storage_input)
                     .with_system(clear_entity)
@@ -37,8 +46,54 @@ impl Plugin for SynthesisPlugin {
 pub struct Table(pub HashMap<Vec<(Type, usize)>, Type>);
 pub struct TableInverse(pub HashMap<usize, Vec<(Type, usize)>>);
 
-fn setup_table(mut commands: Commands) {
-    let t = init_table();
+impl TableInverse {
+    /// Synthesis steps needed to make `item` from basic items, 0 for basic items.
+    pub fn depth(&self, item: Type) -> u32 {
+        // a malformed table could contain a cycle
+        self.bounded_depth(item, self.0.len())
+    }
+
+    fn bounded_depth(&self, item: Type, bound: usize) -> u32 {
+        match self.0.get(&(item as usize)) {
+            Some(ingredients) if bound > 0 => {
+                let deepest = ingredients
+                    .iter()
+                    .map(|(ingredient, _)| self.bounded_depth(*ingredient, bound - 1))
+                    .max();
+                1 + deepest.unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+}
+
+pub struct RecipeHandle(pub Handle<RecipeAsset>);
+
+fn load_recipes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut data_handles: ResMut<DataHandles>,
+) {
+    let handle: Handle<RecipeAsset> = asset_server.load(RECIPES_PATH);
+    data_handles.handles.push(handle.clone_untyped());
+    commands.insert_resource(RecipeHandle(handle));
+}
+
+fn setup_table(
+    mut commands: Commands,
+    recipe_handle: Res<RecipeHandle>,
+    recipes: Res<Assets<RecipeAsset>>,
+) {
+    let t = match recipes.get(&recipe_handle.0) {
+        Some(asset) => asset.recipes.clone(),
+        None => {
+            error!(
+                "no recipes loaded from `{}`, synthesis is disabled",
+                RECIPES_PATH
+            );
+            vec![]
+        }
+    };
     let m = t.clone().into_iter().collect();
     let m_inverse = t.into_iter().map(|(k, v)| (v as usize, k)).collect();
     commands.insert_resource(Table(m));
@@ -46,39 +101,26 @@ fn setup_table(mut commands: Commands) {
 }
 
 fn storage_input(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<StepActions>,
     mut storage_in_hand: ResMut<StorageInHand>,
-    mut q: Query<(&Storage, &mut Blueprint)>,
+    mut q: Query<(&Storage, &mut Blueprint), With<Player>>,
 ) {
-    let keys = vec![
-        KeyCode::Key1,
-        KeyCode::Key2,
-        KeyCode::Key3,
-        KeyCode::Key4,
-        KeyCode::Key5,
-        KeyCode::Key6,
-        KeyCode::Key7,
-        KeyCode::Key8,
-    ];
-    if keyboard_input.any_just_pressed(keys) {
+    let cycle = |step: usize| match storage_in_hand.cur {
+        Some(cur) => (cur + step) % STORAGE_SIZE,
+        None => 0,
+    };
+    let slot = if actions.just_pressed(Action::NextSlot) {
+        Some(cycle(1))
+    } else if actions.just_pressed(Action::PrevSlot) {
+        Some(cycle(STORAGE_SIZE - 1))
+    } else if actions.just_pressed(Action::AddToBlueprint) {
+        storage_in_hand.cur
+    } else {
+        (0..STORAGE_SIZE).find(|&i| actions.just_pressed(Action::Slot(i)))
+    };
+    if slot.is_some() {
         storage_in_hand.prev = storage_in_hand.cur;
-        if keyboard_input.just_pressed(KeyCode::Key1) {
-            storage_in_hand.cur = Some(0);
-        } else if keyboard_input.just_pressed(KeyCode::Key2) {
-            storage_in_hand.cur = Some(1);
-        } else if keyboard_input.just_pressed(KeyCode::Key3) {
-            storage_in_hand.cur = Some(2);
-        } else if keyboard_input.just_pressed(KeyCode::Key4) {
-            storage_in_hand.cur = Some(3);
-        } else if keyboard_input.just_pressed(KeyCode::Key5) {
-            storage_in_hand.cur = Some(4);
-        } else if keyboard_input.just_pressed(KeyCode::Key6) {
-            storage_in_hand.cur = Some(5);
-        } else if keyboard_input.just_pressed(KeyCode::Key7) {
-            storage_in_hand.cur = Some(6);
-        } else if keyboard_input.just_pressed(KeyCode::Key8) {
-            storage_in_hand.cur = Some(7);
-        }
+        storage_in_hand.cur = slot;
         // add to blueprint if double clicked
         if storage_in_hand.prev == storage_in_hand.cur {
             if let Some(idx) = storage_in_hand.prev {
@@ -89,9 +131,11 @@ fn storage_input(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn store_entity(
     mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<StepActions>,
+    mut stats: ResMut<RunStats>,
     mut joint_set: ResMut<ImpulseJointSet>,
     mut island_manager: ResMut<IslandManager>,
     mut entity_in_hand: ResMut<EntityInHand>,
@@ -101,7 +145,7 @@ fn store_entity(
     )>,
     query_id: Query<&Throwable>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::F) {
+    if actions.just_pressed(Action::Store) {
         if let Some(e_in_hand) = entity_in_hand.entity {
             let mut player_query = q.q0();
             let (player_entity, mut storage): (Entity, Mut<Storage>) = player_query.single_mut();
@@ -117,35 +161,44 @@ fn store_entity(
             );
             commands.entity(e_in_hand).despawn();
             entity_in_hand.entity = None;
+            stats.stored += 1;
         }
     }
 }
 
 fn hold_stored_entity(
     mut commands: Commands,
+    registry: Res<ObjectRegistry>,
     sprite_atlas_handle: Res<SpriteAtlasHandle>,
-    windows: Res<Windows>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<StepActions>,
     storage_in_hand: Res<StorageInHand>,
     mut entity_in_hand: ResMut<EntityInHand>,
     mut q: Query<(Entity, &mut Storage, &RigidBodyPositionComponent), With<Player>>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::E) {
+    if !actions.just_pressed(Action::Hold) {
         return;
     }
     let (player_entity, mut storage, rb_pos) = q.single_mut();
     if let Some(i) = storage_in_hand.cur {
         let id = storage.items[i];
         if id != Type::Empty {
-            let window = windows.get_primary().unwrap();
             use nalgebra::UnitComplex;
-            if let Some(pos) = window.cursor_position() {
-                let size = Vec2::new(window.width() as f32, window.height() as f32);
-                let pos = pos - size / 2.0;
+            let dir = match actions.device {
+                Device::KeyboardMouse => actions
+                    .cursor
+                    .map(|cursor| cursor - Vec2::from(rb_pos.position.translation.vector)),
+                // in front of the player
+                Device::Gamepad(_) => Some(Vec2::new(
+                    rb_pos.position.rotation.cos_angle(),
+                    rb_pos.position.rotation.sin_angle(),
+                )),
+            };
+            if let Some(pos) = dir {
                 let cursor_rot = UnitComplex::new(pos.y.atan2(pos.x));
                 let asset_server = sprite_atlas_handle.as_ref();
                 let object_entity = commands
                     .spawn_object(
+                        registry.as_ref(),
                         asset_server,
                         id,
                         [
@@ -154,20 +207,8 @@ fn hold_stored_entity(
                         ],
                     )
                     .id();
-                let axis = Vector::x_axis();
-                let joint = PrismaticJoint::new(axis)
-                    .local_anchor1(point![0.0, 0.0])
-                    .local_anchor2(point![0.0, 0.0])
-                    .limit_axis([6.5, 8.0]);
-                commands.spawn().insert(JointBuilderComponent::new(
-                    joint,
-                    player_entity,
-                    object_entity,
-                ));
+                grab_object(&mut commands, player_entity, object_entity, [6.5, 8.0], true);
                 entity_in_hand.entity = Some(object_entity);
-                commands
-                    .entity(object_entity)
-                    .insert(Grabbed(player_entity));
                 storage.items[i] = Type::Empty;
             }
         }
@@ -197,11 +238,14 @@ fn check_ingredients(sto: &Storage, bp: &Blueprint) -> (bool, Vec<usize>) {
 }
 
 fn synthesize_entity(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<StepActions>,
     table: Res<Table>,
-    mut q: Query<(&mut Storage, &Blueprint)>,
+    table_inverse: Res<TableInverse>,
+    mut score: ResMut<Score>,
+    mut stats: ResMut<RunStats>,
+    mut q: Query<(&mut Storage, &Blueprint), With<Player>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Q) {
+    if actions.just_pressed(Action::Synthesize) {
         let (mut storage, bp): (Mut<Storage>, &Blueprint) = q.single_mut();
         let bp_vec: std::vec::Vec<(Type, usize)> = bp.clone().into();
         match table.0.get(&bp_vec) {
@@ -210,6 +254,8 @@ fn synthesize_entity(
                 if is_enough {
                     storage.remove(&indices);
                     storage.insert(id);
+                    score.add_synthesized(table_inverse.depth(id));
+                    *stats.syntheses.entry(id).or_default() += 1;
                 }
             }
             None => {}
@@ -217,9 +263,103 @@ fn synthesize_entity(
     }
 }
 
-fn clear_entity(keyboard_input: Res<Input<KeyCode>>, mut bp_query: Query<&mut Blueprint>) {
-    if keyboard_input.just_pressed(KeyCode::C) {
+fn clear_entity(
+    actions: Res<StepActions>,
+    mut bp_query: Query<&mut Blueprint, With<Player>>,
+) {
+    if actions.just_pressed(Action::Clear) {
         let mut bp = bp_query.single_mut();
         bp.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestApp;
+
+    fn storage(app: &mut TestApp) -> Vec<Type> {
+        let player = app.player();
+        app.get::<Storage>(player).unwrap().items.clone()
+    }
+
+    fn blueprint(app: &mut TestApp) -> Vec<Type> {
+        let player = app.player();
+        app.get::<Blueprint>(player).unwrap().items.clone()
+    }
+
+    /// Put `items` in the first storage slots, as if they had been stored.
+    fn fill_storage(app: &mut TestApp, items: &[Type]) {
+        let player = app.player();
+        let mut storage = app.get_mut::<Storage>(player).unwrap();
+        storage.items[..items.len()].copy_from_slice(items);
+    }
+
+    #[test]
+    fn store_puts_the_held_object_in_storage() {
+        let mut app = TestApp::new();
+        let held = app.resource::<EntityInHand>().entity.unwrap();
+        app.tap(KeyCode::F);
+        assert_eq!(storage(&mut app)[0], Type::Square);
+        assert!(app.resource::<EntityInHand>().entity.is_none());
+        assert!(app.app.world.get_entity(held).is_none());
+        assert_eq!(app.resource::<RunStats>().stored, 1);
+    }
+
+    #[test]
+    fn hold_takes_the_selected_item_out_of_storage() {
+        let mut app = TestApp::new();
+        app.tap(KeyCode::F);
+        app.tap(KeyCode::Key1);
+        app.aim_at(Vec2::new(10.0, -10.0));
+        app.tap(KeyCode::E);
+        assert_eq!(storage(&mut app)[0], Type::Empty);
+        let held = app.resource::<EntityInHand>().entity.unwrap();
+        assert_eq!(app.get::<Throwable>(held).unwrap().0, Type::Square);
+        assert!(app.get::<Grabbed>(held).is_some());
+    }
+
+    #[test]
+    fn synthesize_replaces_the_ingredients_with_the_product() {
+        let mut app = TestApp::new();
+        fill_storage(&mut app, &[Type::Square, Type::Square]);
+        // selecting a slot twice adds it to the blueprint
+        for key in [KeyCode::Key1, KeyCode::Key1, KeyCode::Key2, KeyCode::Key2] {
+            app.tap(key);
+        }
+        app.tap(KeyCode::Q);
+        let items = storage(&mut app);
+        assert!(items.contains(&Type::Rect));
+        assert!(!items.contains(&Type::Square));
+        assert_eq!(app.resource::<RunStats>().syntheses.get(&Type::Rect), Some(&1));
+    }
+
+    #[test]
+    fn clear_empties_the_blueprint() {
+        let mut app = TestApp::new();
+        fill_storage(&mut app, &[Type::Square]);
+        app.tap(KeyCode::Key1);
+        app.tap(KeyCode::Key1);
+        assert_eq!(blueprint(&mut app)[0], Type::Square);
+        app.tap(KeyCode::C);
+        assert!(blueprint(&mut app).iter().all(|item| *item == Type::Empty));
+        assert_eq!(storage(&mut app)[0], Type::Square);
+    }
+
+    #[test]
+    fn synthesis_leaves_enemies_alone() {
+        let mut app = TestApp::new();
+        // enemies have a storage and a blueprint too
+        let enemy = app.spawn_enemy(Vec2::new(80.0, 40.0));
+        fill_storage(&mut app, &[Type::Square, Type::Square]);
+        for key in [KeyCode::Key1, KeyCode::Key1, KeyCode::Key2, KeyCode::Key2] {
+            app.tap(key);
+        }
+        app.tap(KeyCode::Q);
+        app.tap(KeyCode::C);
+        assert!(storage(&mut app).contains(&Type::Rect));
+        assert!(blueprint(&mut app).iter().all(|item| *item == Type::Empty));
+        let enemy_storage = &app.get::<Storage>(enemy).unwrap().items;
+        assert!(enemy_storage.iter().all(|item| *item == Type::Empty));
+    }
+}
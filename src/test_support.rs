@@ -0,0 +1,191 @@
+use bevy::app::Events;
+use bevy::ecs::system::CommandQueue;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::window::CursorMoved;
+use bevy_rapier2d::prelude::*;
+
+use super::{AppState, SpriteAtlasHandle};
+use crate::action::{ActionMap, ActionPlugin, ActionState};
+use crate::bundle::CommandsSpawner;
+use crate::component::Player;
+use crate::damage::{DamageEvent, DamageKind};
+use crate::headless::simulation_app;
+use crate::object_def::ObjectRegistry;
+use crate::rng::GameRng;
+use crate::shape_mod::Type;
+
+/// Updates to wait for the data assets before giving up on a test.
+const READY_UPDATES: u32 = 5000;
+/// Seed of every test run, so spawns don't vary between runs of a test.
+const TEST_SEED: u64 = 0;
+
+/// A headless game in a run, driven by the keyboard. Every `step` updates the app once, which
+/// runs one simulation step. The run starts as usual: the player holds a Square.
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        let mut app = simulation_app();
+        // no window: the keyboard is read as usual, the cursor is set by `aim_at`
+        app.add_plugin(InputPlugin)
+            .add_event::<CursorMoved>()
+            .init_resource::<Windows>()
+            .add_plugin(ActionPlugin)
+            // a controls file of the working directory must not change the keys of the tests
+            .insert_resource(ActionMap::default())
+            .insert_resource(GameRng::new(Some(TEST_SEED)))
+            .init_resource::<TestCursor>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                test_cursor_system.after("action_state"),
+            );
+        let mut test_app = TestApp { app };
+        for _ in 0..READY_UPDATES {
+            test_app.app.update();
+            if test_app.state() == AppState::InGame && test_app.try_player().is_some() {
+                return test_app;
+            }
+            // data assets load on other threads
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("the run didn't start after {} updates", READY_UPDATES);
+    }
+
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    pub fn steps(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Step until `done` holds, for at most `max_steps` steps. Returns whether it held.
+    pub fn step_until(&mut self, max_steps: u32, done: impl Fn(&Self) -> bool) -> bool {
+        for _ in 0..max_steps {
+            self.step();
+            if done(self) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Hold `key` from the next step on.
+    pub fn press(&mut self, key: KeyCode) {
+        self.keys().press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.keys().release(key);
+    }
+
+    /// Press `key` for one step and release it on the next.
+    pub fn tap(&mut self, key: KeyCode) {
+        self.press(key);
+        self.step();
+        self.release(key);
+        self.step();
+    }
+
+    /// Move the cursor to `cursor`, in rapier units.
+    pub fn aim_at(&mut self, cursor: Vec2) {
+        self.app.world.insert_resource(TestCursor(Some(cursor)));
+    }
+
+    pub fn player(&mut self) -> Entity {
+        self.try_player().expect("the run has a player")
+    }
+
+    fn try_player(&mut self) -> Option<Entity> {
+        let mut query = self.app.world.query_filtered::<Entity, With<Player>>();
+        query.iter(&self.app.world).next()
+    }
+
+    /// Spawn an object as the game does. Its rigid body is created by the next step.
+    pub fn spawn_object(&mut self, id: Type, pos: [f32; 2]) -> Entity {
+        self.spawn(|commands, registry, sprite_atlas_handle| {
+            commands
+                .spawn_object(registry, sprite_atlas_handle, id, pos)
+                .id()
+        })
+    }
+
+    /// Spawn an enemy as the game does, empty-handed.
+    pub fn spawn_enemy(&mut self, pos: Vec2) -> Entity {
+        self.spawn(|commands, _, sprite_atlas_handle| {
+            commands.spawn_enemy(sprite_atlas_handle, pos.x, pos.y).id()
+        })
+    }
+
+    fn spawn(
+        &mut self,
+        spawn: impl FnOnce(&mut Commands, &ObjectRegistry, &SpriteAtlasHandle) -> Entity,
+    ) -> Entity {
+        let mut queue = CommandQueue::default();
+        let entity = {
+            let world = &self.app.world;
+            let registry = world.get_resource::<ObjectRegistry>().expect("objects are defined");
+            let sprite_atlas_handle = world
+                .get_resource::<SpriteAtlasHandle>()
+                .expect("the atlas handle is initialized");
+            spawn(&mut Commands::new(&mut queue, world), registry, sprite_atlas_handle)
+        };
+        queue.apply(&mut self.app.world);
+        entity
+    }
+
+    /// Deal `amount` of sourceless impact damage to `target` in the next step.
+    pub fn damage(&mut self, target: Entity, amount: i32) {
+        self.resource_mut::<Events<DamageEvent>>().send(DamageEvent {
+            target,
+            source: None,
+            amount,
+            kind: DamageKind::Impact,
+        });
+    }
+
+    /// Position of the rigid body of `entity`, in rapier units.
+    pub fn position(&self, entity: Entity) -> Vec2 {
+        let pos = self
+            .get::<RigidBodyPositionComponent>(entity)
+            .expect("the entity has a rigid body");
+        Vec2::from(pos.position.translation.vector)
+    }
+
+    pub fn resource<R: Send + Sync + 'static>(&self) -> &R {
+        self.app.world.get_resource::<R>().expect("the resource exists")
+    }
+
+    pub fn resource_mut<R: Send + Sync + 'static>(&mut self) -> Mut<R> {
+        self.app.world.get_resource_mut::<R>().expect("the resource exists")
+    }
+
+    pub fn get<C: Component>(&self, entity: Entity) -> Option<&C> {
+        self.app.world.get::<C>(entity)
+    }
+
+    pub fn get_mut<C: Component>(&mut self, entity: Entity) -> Option<Mut<C>> {
+        self.app.world.get_mut::<C>(entity)
+    }
+
+    pub fn state(&self) -> AppState {
+        self.resource::<State<AppState>>().current().clone()
+    }
+
+    fn keys(&mut self) -> Mut<Input<KeyCode>> {
+        self.resource_mut::<Input<KeyCode>>()
+    }
+}
+
+/// Where the cursor of a test is, in place of a window's.
+#[derive(Default)]
+struct TestCursor(Option<Vec2>);
+
+fn test_cursor_system(cursor: Res<TestCursor>, mut actions: ResMut<ActionState>) {
+    actions.cursor = cursor.0;
+}